@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use eyre::{ensure, Result, WrapErr};
+use sui_sdk::{SuiClient, SuiClientBuilder};
+use tracing::{instrument, warn};
+
+use crate::config::SuiConfig;
+
+/// Connection to Sui node of the configured chain. Doesn't need keys, so it's enough for
+/// commands which only read objects.
+pub struct ChainClient {
+    pub client: Arc<SuiClient>,
+    pub chain_id: String,
+    /// Directory of config file, deployment records and snapshots are kept relative to it.
+    pub base_dir: PathBuf,
+}
+
+impl ChainClient {
+    /// Connects to the node and makes sure it reports configured chain id, which is required for
+    /// production profiles.
+    #[instrument(name = "Connecting to Sui node", skip_all)]
+    pub async fn connect(config: &SuiConfig) -> Result<Self> {
+        ensure!(
+            !config.production || config.chain_id.is_some(),
+            "Production profile must declare `chain_id`"
+        );
+        let client = SuiClientBuilder::default()
+            .build(config.node_url.clone())
+            .await
+            .wrap_err("Failed to connect to Sui Node")?;
+
+        let chain_id = client
+            .read_api()
+            .get_chain_identifier()
+            .await
+            .wrap_err("Failed to get chain identifier")?;
+        match config.chain_id.as_deref() {
+            None => warn!("`chain_id` isn't configured, node reports `{chain_id}`"),
+            Some(expected) => ensure!(
+                expected == chain_id,
+                "Node at {} reports chain id `{chain_id}`, but `{expected}` is expected",
+                config.node_url
+            ),
+        }
+
+        Ok(Self {
+            client: Arc::new(client),
+            chain_id,
+            base_dir: config.base_dir.clone(),
+        })
+    }
+}
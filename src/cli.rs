@@ -0,0 +1,202 @@
+use crate::config::{BuildOptions, KeyScheme, LogFormat};
+use crate::constants::{CONFIG_PATH, WATCH_POLL_INTERVAL_MS};
+use crate::inspect::OutputFormat;
+use clap::{Args, Parser, Subcommand};
+use eyre::{eyre, Result};
+use std::path::PathBuf;
+use sui_types::base_types::ObjectID;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Config file, relative paths in it are resolved against its directory.
+    #[arg(long, global = true, default_value = CONFIG_PATH)]
+    pub config: PathBuf,
+    /// Console log format, overrides `log.format` from config.
+    #[arg(long, global = true, value_enum)]
+    pub log_format: Option<LogFormat>,
+    /// File to write JSON logs into, overrides `log.file` from config.
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+    /// OTLP collector endpoint to export spans into, overrides `log.otlp_endpoint` from config.
+    #[arg(long, global = true)]
+    pub otlp_endpoint: Option<String>,
+    /// Confirm sending transactions to a profile marked as production without prompt.
+    #[arg(long, global = true)]
+    pub confirm_production: bool,
+    #[command(flatten)]
+    pub build: BuildArgs,
+}
+
+/// Move build options, override `sui.build` from config.
+#[derive(Args, Debug)]
+pub struct BuildArgs {
+    /// Build in dev mode.
+    #[arg(long, global = true)]
+    pub dev: bool,
+    /// Build in test mode.
+    #[arg(long, global = true)]
+    pub test: bool,
+    /// Named address override in `name=address` format, can be repeated.
+    #[arg(long = "named-address", global = true)]
+    pub named_addresses: Vec<String>,
+    /// Don't fetch the latest version of git dependencies.
+    #[arg(long, global = true)]
+    pub skip_fetch_latest_git_deps: bool,
+    /// Silence compiler warnings and lints.
+    #[arg(long, global = true)]
+    pub no_lint: bool,
+    /// Whether to bundle modules of unpublished dependencies.
+    #[arg(long, global = true)]
+    pub with_unpublished_dependencies: Option<bool>,
+}
+
+impl BuildArgs {
+    /// Overrides build options from config with the ones provided in command line.
+    pub fn apply_to(&self, options: &mut BuildOptions) -> Result<()> {
+        options.dev |= self.dev;
+        options.test |= self.test;
+        options.skip_fetch_latest_git_deps |= self.skip_fetch_latest_git_deps;
+        options.lint &= !self.no_lint;
+        if let Some(with_unpublished_dependencies) = self.with_unpublished_dependencies {
+            options.with_unpublished_dependencies = with_unpublished_dependencies;
+        }
+        for named_address in &self.named_addresses {
+            let (name, address) = named_address
+                .split_once('=')
+                .ok_or_else(|| eyre!("Named address must be in `name=address` format"))?;
+            options
+                .named_addresses
+                .insert(name.to_string(), address.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Publish and setup the Move package (default).
+    Deploy {
+        /// Publish even if bytecode is unchanged since the last deployment.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Convert plaintext Sui keystore into encrypted keystore.
+    EncryptKeystore {
+        /// Sui keystore to convert, defaults to the keystore from config.
+        #[arg(long)]
+        input: Option<PathBuf>,
+        /// Where to write encrypted keystore.
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Check node, keystore, package and balance without sending transactions.
+    Preflight,
+    /// Request gas from configured faucet if balance is below estimated deployment cost.
+    Faucet,
+    /// Merge all coins of the type owned by active address into one coin.
+    MergeCoins {
+        #[arg(long, default_value = "0x2::sui::SUI")]
+        coin_type: String,
+    },
+    /// Split gas owner's coins into new coins with provided amounts.
+    SplitCoin {
+        #[arg(required = true)]
+        amounts: Vec<u64>,
+    },
+    /// Make sure gas owner has at least `count` coins of at least `amount`, splitting coins if
+    /// needed, e.g. before running transactions in parallel.
+    EnsureGasCoins {
+        #[arg(long)]
+        count: usize,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Fetch an object by id or by key of the last deployment record and print it.
+    Inspect {
+        /// Object id or key, e.g. `lemon_registry`.
+        target: String,
+        #[arg(long, value_enum, default_value = "tree")]
+        format: OutputFormat,
+    },
+    /// Enumerate dynamic fields of an object with decoded values.
+    DynamicFields {
+        /// Object id or key of the last deployment record, e.g. `lemon_registry`.
+        target: String,
+        /// Cursor returned with the previous page.
+        #[arg(long)]
+        cursor: Option<ObjectID>,
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Fetch all pages.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Write full state of deployed objects, including dynamic fields, into JSON snapshot.
+    Snapshot {
+        /// Defaults to `snapshots/{chain_id}-{timestamp}.json` next to config file.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Print events emitted by the deployed package as JSON lines until interrupted.
+    Watch {
+        /// Only events of this module.
+        #[arg(long)]
+        module: Option<String>,
+        /// Only events of this type, struct name or full type.
+        #[arg(long)]
+        event_type: Option<String>,
+        /// Append events into the file instead of stdout.
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Where to persist the cursor, defaults to `deployments/{chain_id}.cursor.json` next to
+        /// config file.
+        #[arg(long)]
+        cursor_file: Option<PathBuf>,
+        #[arg(long, default_value_t = WATCH_POLL_INTERVAL_MS)]
+        interval_ms: u64,
+    },
+    /// Manage keys in the configured keystore.
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeysCommand {
+    /// Generate new key and add it into keystore.
+    Generate {
+        #[arg(long, value_enum, default_value = "ed25519")]
+        scheme: KeyScheme,
+        #[arg(long)]
+        derivation_path: Option<String>,
+    },
+    /// Import key from mnemonic phrase, the phrase is prompted.
+    Import {
+        #[arg(long, value_enum, default_value = "ed25519")]
+        scheme: KeyScheme,
+        #[arg(long)]
+        derivation_path: Option<String>,
+    },
+    /// List addresses from keystore with their SUI balances.
+    List,
+    /// Set active address in config.
+    SetActive { address: String },
+}
+
+impl Command {
+    pub fn sends_transactions(&self) -> bool {
+        matches!(
+            self,
+            Command::Deploy { .. }
+                | Command::Faucet
+                | Command::MergeCoins { .. }
+                | Command::SplitCoin { .. }
+                | Command::EnsureGasCoins { .. }
+        )
+    }
+}
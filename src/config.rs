@@ -1,12 +1,85 @@
-use crate::constants::CONFIG_PATH;
-use eyre::{eyre, Result, WrapErr};
+use crate::constants::{
+    GIT_CACHE_DIR, PUBLISH_PACKAGE_GAS_BUDGET, SETUP_PACKAGE_GAS_BUDGET, TRANSFERABLE_CAPS,
+    TRANSFER_CAPS_GAS_BUDGET,
+};
+use clap::ValueEnum;
+use eyre::{ensure, eyre, Result, WrapErr};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::SignatureScheme;
 use tracing::instrument;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppConfig {
+    /// Path of the file config was loaded from.
+    #[serde(skip)]
+    pub path: PathBuf,
     pub sui: SuiConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+    /// Faucet to fund gas owner from, usually configured for localnet and devnet only.
+    pub faucet: Option<FaucetConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FaucetConfig {
+    pub url: String,
+    /// How long to wait for coins from faucet to appear.
+    #[serde(default = "default_faucet_timeout_secs")]
+    pub timeout_secs: u64,
+    /// How many times to ask faucet until balance covers the required amount.
+    #[serde(default = "default_faucet_max_requests")]
+    pub max_requests: u32,
+}
+
+fn default_faucet_timeout_secs() -> u64 {
+    60
+}
+
+fn default_faucet_max_requests() -> u32 {
+    5
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Default filter directives, overridden by `RUST_LOG`.
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    /// File to write JSON logs into in addition to the console output, relative to the
+    /// directory of config file.
+    pub file: Option<String>,
+    /// OTLP gRPC endpoint of trace collector, e.g. `http://localhost:4317`.
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            level: default_log_level(),
+            file: None,
+            otlp_endpoint: None,
+        }
+    }
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    Pretty,
+    Compact,
+    #[default]
+    Json,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -14,7 +87,142 @@ pub struct SuiConfig {
     pub config_path: String,
     pub keystore_filename: String,
     pub node_url: String,
+    /// Chain identifier the node at `node_url` is expected to report.
+    pub chain_id: Option<String>,
+    /// Production profiles require explicit confirmation before sending transactions.
+    #[serde(default)]
+    pub production: bool,
+    /// Path to the main Move package, relative to config file.
+    #[serde(default)]
     pub move_package_path: String,
+    /// Git repository to take the main Move package from instead of `move_package_path`.
+    pub move_package_git: Option<GitSource>,
+    /// Directory relative paths are resolved against, the directory of config file.
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+    /// Options for building Move packages.
+    #[serde(default)]
+    pub build: BuildOptions,
+    /// Packages published before the main package, in order of their dependencies.
+    #[serde(default)]
+    pub packages: Vec<PackageConfig>,
+    /// Where to take keys from, defaults to the Sui keystore in home directory.
+    #[serde(default)]
+    pub keystore_source: KeystoreSource,
+    /// Address from the keystore used as sender, defaults to the last key in keystore.
+    #[serde(default)]
+    pub active_address: Option<String>,
+    /// Address from the keystore whose coins pay for gas instead of the active address.
+    #[serde(default)]
+    pub sponsor_address: Option<String>,
+    /// How to pick coins for gas payment.
+    #[serde(default)]
+    pub gas_selection: GasSelectionStrategy,
+    /// Caps transferred after setup, by field of publish result, e.g. `lemon_cap = "0x..."`.
+    #[serde(default)]
+    pub cap_owners: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BuildOptions {
+    /// Use `dev-addresses` and `dev-dependencies` of packages.
+    pub dev: bool,
+    /// Compile `#[test_only]` code.
+    pub test: bool,
+    /// Named addresses in addition to the ones declared in `Move.toml`.
+    pub named_addresses: BTreeMap<String, String>,
+    /// Don't fetch the latest version of git dependencies.
+    pub skip_fetch_latest_git_deps: bool,
+    /// Report compiler warnings and lints, otherwise they are silenced.
+    pub lint: bool,
+    /// Bundle modules of unpublished dependencies into the published package.
+    pub with_unpublished_dependencies: bool,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            dev: false,
+            test: false,
+            named_addresses: BTreeMap::new(),
+            skip_fetch_latest_git_deps: false,
+            lint: true,
+            with_unpublished_dependencies: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GitSource {
+    pub url: String,
+    /// Commit, tag or branch to check out, pin a commit for reproducible deployments.
+    pub rev: String,
+    /// Path to the package inside repository.
+    pub subdir: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PackageConfig {
+    pub name: String,
+    pub path: String,
+    /// Names of other packages from `packages` this one depends on.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GasSelectionStrategy {
+    /// The smallest coin covering the budget, or as few coins as possible adding up to it.
+    #[default]
+    SmallestSufficient,
+    /// The largest coins first.
+    Largest,
+    /// As few coins as possible, preferring smaller ones among them.
+    Fewest,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeystoreSource {
+    /// Keystore at `~/{config_path}/{keystore_filename}`.
+    #[default]
+    Home,
+    /// Keystore at the provided path, relative to the directory of config file.
+    File { path: String },
+    /// Keystore encrypted by `encrypt-keystore` command, password is read from
+    /// `password_var` env var or prompted.
+    Encrypted {
+        path: String,
+        password_var: Option<String>,
+    },
+    /// Base64 encoded private key (flag || key) stored in environment variable.
+    Env { var: String },
+    /// Mnemonic phrase stored in environment variable.
+    Mnemonic {
+        var: String,
+        #[serde(default)]
+        key_scheme: KeyScheme,
+        derivation_path: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyScheme {
+    #[default]
+    Ed25519,
+    Secp256k1,
+}
+
+impl From<KeyScheme> for SignatureScheme {
+    fn from(value: KeyScheme) -> Self {
+        match value {
+            KeyScheme::Ed25519 => SignatureScheme::ED25519,
+            KeyScheme::Secp256k1 => SignatureScheme::Secp256k1,
+        }
+    }
 }
 
 impl SuiConfig {
@@ -27,28 +235,156 @@ impl SuiConfig {
         Ok(ret)
     }
 
+    /// Path to the main Move package, cloned into cache first if it comes from git.
     pub fn move_package_path(&self) -> Result<PathBuf> {
-        let ret = std::env::current_dir()
-            .wrap_err("Failed to get current directory")?
-            .parent()
-            .ok_or_else(|| eyre!("Failed to get parent directory"))?
-            .join(self.move_package_path.as_str());
+        match &self.move_package_git {
+            None => self.resolve_path(&self.move_package_path),
+            Some(git) => git.checkout(),
+        }
+    }
+
+    /// New owners of caps by field of publish result, checked against caps which can be
+    /// transferred.
+    pub fn cap_owners(&self) -> Result<BTreeMap<String, SuiAddress>> {
+        let mut ret = BTreeMap::new();
+        for (cap, owner) in &self.cap_owners {
+            ensure!(
+                TRANSFERABLE_CAPS.contains(&cap.as_str()),
+                "Unknown cap `{cap}`, expected one of {TRANSFERABLE_CAPS:?}"
+            );
+            let owner = SuiAddress::from_str(owner)
+                .map_err(|e| eyre!(e))
+                .wrap_err_with(|| format!("Failed to parse owner of `{cap}`"))?;
+            ret.insert(cap.clone(), owner);
+        }
 
         Ok(ret)
     }
+
+    /// Gas budgets of all deployment transactions: publishing of packages from config and of the
+    /// main package, its setup and transfer of caps.
+    pub fn estimated_deployment_cost(&self) -> u64 {
+        let publish = PUBLISH_PACKAGE_GAS_BUDGET * (self.packages.len() as u64 + 1);
+        let transfer_caps = if self.cap_owners.is_empty() {
+            0
+        } else {
+            TRANSFER_CAPS_GAS_BUDGET
+        };
+
+        publish + SETUP_PACKAGE_GAS_BUDGET + transfer_caps
+    }
+
+    /// Resolves `path` relative to the directory of config file. Absolute paths are kept as is
+    /// and `~` is expanded to home directory.
+    pub fn resolve_path(&self, path: &str) -> Result<PathBuf> {
+        if path == "~" || path.starts_with("~/") {
+            let ret = dirs::home_dir()
+                .ok_or_else(|| eyre!("Failed to get home directory"))?
+                .join(path.trim_start_matches('~').trim_start_matches('/'));
+
+            return Ok(ret);
+        }
+
+        Ok(self.base_dir.join(path))
+    }
+}
+
+impl GitSource {
+    /// Clones repository into cache, if it isn't there yet, and checks out pinned revision.
+    /// Branches and tags are fetched on every checkout, commits only if they aren't cached.
+    #[instrument(name = "Checking out Move package from git")]
+    pub fn checkout(&self) -> Result<PathBuf> {
+        let url_hash = format!("{:x}", Sha256::digest(self.url.as_bytes()));
+        let repo_dir = dirs::cache_dir()
+            .ok_or_else(|| eyre!("Failed to get cache directory"))?
+            .join(GIT_CACHE_DIR)
+            .join(&url_hash[..16]);
+
+        if !repo_dir.exists() {
+            let parent = repo_dir
+                .parent()
+                .ok_or_else(|| eyre!("Failed to get parent directory"))?;
+            std::fs::create_dir_all(parent).wrap_err("Failed to create git cache directory")?;
+            run_git(
+                parent,
+                &[
+                    "clone",
+                    "--no-checkout",
+                    &self.url,
+                    &repo_dir.to_string_lossy(),
+                ],
+            )?;
+        }
+
+        let is_commit = self.rev.len() == 40 && self.rev.chars().all(|c| c.is_ascii_hexdigit());
+        let is_cached = is_commit
+            && run_git(
+                &repo_dir,
+                &["cat-file", "-e", &format!("{}^{{commit}}", self.rev)],
+            )
+            .is_ok();
+        let revision = if is_cached {
+            self.rev.as_str()
+        } else {
+            run_git(&repo_dir, &["fetch", "origin", &self.rev])?;
+            "FETCH_HEAD"
+        };
+        run_git(&repo_dir, &["checkout", "--detach", "--force", revision])?;
+
+        Ok(match &self.subdir {
+            None => repo_dir,
+            Some(subdir) => repo_dir.join(subdir),
+        })
+    }
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = std::process::Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .wrap_err("Failed to run git")?;
+
+    ensure!(
+        output.status.success(),
+        "`git {}` failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    Ok(())
 }
 
 #[instrument(name = "Loading config")]
-pub fn load_config() -> Result<AppConfig> {
-    let config_path = std::env::current_dir()
+pub fn load_config(path: &Path) -> Result<AppConfig> {
+    let path = std::env::current_dir()
         .wrap_err("Failed to determine the current directory")?
-        .join(CONFIG_PATH);
+        .join(path);
 
     let ret = config::Config::builder()
-        .add_source(config::File::from(config_path))
+        .add_source(config::File::from(path.as_path()))
         .build()
         .wrap_err("Failed to build config")?;
 
-    ret.try_deserialize()
-        .wrap_err("Failed to deserialize config into struct `Config`")
+    let mut ret: AppConfig = ret
+        .try_deserialize()
+        .wrap_err("Failed to deserialize config into struct `Config`")?;
+    ret.sui.base_dir = path
+        .parent()
+        .ok_or_else(|| eyre!("Failed to get directory of config file"))?
+        .to_path_buf();
+    ret.path = path;
+
+    Ok(ret)
+}
+
+/// Writes `active_address` into config file, keeping the rest of the file untouched.
+#[instrument(name = "Setting active address")]
+pub fn set_active_address(config_path: &Path, address: &str) -> Result<()> {
+    let content = std::fs::read_to_string(config_path).wrap_err("Failed to read config file")?;
+    let mut document = content
+        .parse::<toml_edit::Document>()
+        .wrap_err("Failed to parse config file")?;
+    document["sui"]["active_address"] = toml_edit::value(address);
+    std::fs::write(config_path, document.to_string()).wrap_err("Failed to write config file")
 }
@@ -1,5 +1,27 @@
 pub const PUBLISH_PACKAGE_GAS_BUDGET: u64 = 160_000_000;
 pub const SETUP_PACKAGE_GAS_BUDGET: u64 = 10_000;
-pub const MERGE_SUI_GAS_BUDGET: u64 = 200_000;
+pub const FAUCET_POLL_INTERVAL_MS: u64 = 1_000;
+pub const MERGE_COINS_GAS_BUDGET: u64 = 400_000;
+/// Added to `MERGE_COINS_GAS_BUDGET` for every merged coin.
+pub const MERGE_COINS_GAS_BUDGET_PER_COIN: u64 = 20_000;
+/// Protocol allows at most 512 arguments per command.
+pub const MAX_MERGE_COINS_PER_COMMAND: usize = 500;
+/// Protocol allows at most 2048 input objects per transaction, some are left for gas payment.
+pub const MAX_MERGE_COINS_PER_TX: usize = 1_500;
+pub const TRANSFER_CAPS_GAS_BUDGET: u64 = 10_000;
+pub const TRANSFERABLE_CAPS: [&str; 3] = ["lemon_cap", "juice_cap", "coin_juice_treasury_cap"];
+pub const SPLIT_COIN_GAS_BUDGET: u64 = 200_000;
 pub const CONFIG_PATH: &str = "./config.toml";
+pub const GIT_CACHE_DIR: &str = "contracts_deployer/git";
 pub const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+pub const KEYSTORE_PASSWORD_ENV: &str = "DEPLOYER_KEYSTORE_PASSWORD";
+pub const ENCRYPTED_KEYSTORE_VERSION: u8 = 1;
+pub const SCRYPT_LOG_N: u8 = 15;
+pub const SCRYPT_R: u32 = 8;
+pub const SCRYPT_P: u32 = 1;
+pub const DEPLOYMENTS_DIR: &str = "deployments";
+pub const MAX_GAS_PAYMENT_OBJECTS: usize = 256;
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+pub const SNAPSHOT_VERSION: u32 = 1;
+pub const WATCH_PAGE_LIMIT: usize = 50;
+pub const WATCH_POLL_INTERVAL_MS: u64 = 2_000;
@@ -1,29 +1,48 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
-use eyre::{ensure, eyre, Result, WrapErr};
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+use move_core_types::account_address::AccountAddress;
 use move_core_types::identifier::Identifier;
 use move_core_types::language_storage::TypeTag;
+use sha2::{Digest, Sha256};
 use shared_crypto::intent::Intent;
 use sui_framework::build_move_package;
-use sui_keys::keystore::{AccountKeystore, FileBasedKeystore, Keystore};
+use sui_framework_build::compiled_package::BuildConfig;
+use sui_keys::keystore::{AccountKeystore, Keystore};
+use sui_protocol_config::ProtocolConfig;
 use sui_sdk::rpc_types::{
-    Balance, Coin, OwnedObjectRef, SuiObjectDataOptions, SuiObjectResponse,
-    SuiTransactionBlockEffectsV1, SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
+    Balance, Coin, OwnedObjectRef, SuiEvent, SuiObjectDataOptions, SuiObjectResponse,
+    SuiTransactionBlockEffects, SuiTransactionBlockEffectsV1, SuiTransactionBlockResponse,
+    SuiTransactionBlockResponseOptions,
 };
-use sui_sdk::{SuiClient, SuiClientBuilder};
+use sui_sdk::SuiClient;
 use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
 use sui_types::crypto::{EmptySignInfo, Signature};
 use sui_types::message_envelope::VerifiedEnvelope;
-use sui_types::messages::{CallArg, ObjectArg, SenderSignedData, Transaction, TransactionData};
+use sui_types::messages::{
+    Argument, CallArg, Command, ObjectArg, ProgrammableTransaction, SenderSignedData, Transaction,
+    TransactionData,
+};
 use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
-use tracing::{info, instrument};
+use tokio::time::Instant;
+use tracing::{field, info, instrument, Instrument, Span};
 
-use crate::config::AppConfig;
+use crate::chain_client::ChainClient;
+use crate::config::{AppConfig, BuildOptions, SuiConfig};
 use crate::constants::{
-    MERGE_SUI_GAS_BUDGET, PUBLISH_PACKAGE_GAS_BUDGET, SETUP_PACKAGE_GAS_BUDGET,
+    FAUCET_POLL_INTERVAL_MS, MAX_MERGE_COINS_PER_COMMAND, MAX_MERGE_COINS_PER_TX,
+    MERGE_COINS_GAS_BUDGET, MERGE_COINS_GAS_BUDGET_PER_COIN, PUBLISH_PACKAGE_GAS_BUDGET,
+    SETUP_PACKAGE_GAS_BUDGET, SPLIT_COIN_GAS_BUDGET, SUI_COIN_TYPE, TRANSFER_CAPS_GAS_BUDGET,
 };
+use crate::events::EventRecord;
+use crate::faucet;
+use crate::gas_report::GasReport;
+use crate::gas_selection::select_gas_coins;
+use crate::keystore::load_keystore;
 use crate::publish_result::PublishResult;
 use crate::transaction::{AssertSuccess, TryIntoEffects};
 
@@ -31,154 +50,393 @@ pub struct Deployer {
     pub keystore: Keystore,
     pub client: Arc<SuiClient>,
     pub active_address: SuiAddress,
+    pub gas_owner: SuiAddress,
     pub config: AppConfig,
+    pub gas_report: GasReport,
+    /// Events emitted by transactions executed by this deployer, along with the step.
+    pub events: Vec<(String, SuiEvent)>,
+    pub chain_id: String,
 }
 
 impl Deployer {
     #[instrument(name = "Creating Deployer", skip_all)]
     pub async fn build(config: AppConfig) -> Result<Self> {
-        let keystore_path = config
-            .sui
-            .keystore_path()
-            .wrap_err("Failed to get keystore path")?;
-
-        let keystore: Keystore = FileBasedKeystore::new(&keystore_path)
-            .map_err(|e| eyre!(e))?
-            .into();
+        let keystore = load_keystore(&config.sui).wrap_err("Failed to load keystore")?;
 
-        let sui_client = SuiClientBuilder::default()
-            .build(config.sui.node_url.clone())
-            .await
-            .wrap_err("Failed to connect to Sui Node")?;
+        let ChainClient {
+            client, chain_id, ..
+        } = ChainClient::connect(&config.sui).await?;
 
-        let active_address = *keystore.addresses().last().unwrap();
+        let (active_address, gas_owner) = resolve_addresses(&keystore, &config.sui)?;
         info!("Active address is {active_address}");
+        if gas_owner != active_address {
+            info!("Gas is sponsored by {gas_owner}");
+        }
 
         Ok(Self {
             keystore,
-            client: Arc::new(sui_client),
+            client,
             active_address,
+            gas_owner,
             config: config.clone(),
+            gas_report: GasReport::default(),
+            events: Vec::new(),
+            chain_id,
         })
     }
 
-    #[instrument(name = "Looking for coin for gas budget", skip(self))]
-    pub async fn find_gas_coin_to_pay_gas_budget(&self, amount: u64) -> Result<(Coin, Vec<Coin>)> {
-        let mut gas_coins = self
-            .get_sui_coins()
+    pub fn is_sponsored(&self) -> bool {
+        self.gas_owner != self.active_address
+    }
+
+    /// Returns coins to pay `amount` of gas with and the rest of gas owner's coins.
+    #[instrument(name = "Looking for coins for gas budget", skip(self))]
+    pub async fn find_gas_coins_to_pay_gas_budget(
+        &self,
+        amount: u64,
+        exclude: &[ObjectID],
+    ) -> Result<(Vec<Coin>, Vec<Coin>)> {
+        let gas_coins = self
+            .get_sui_coins(self.gas_owner)
             .await
             .wrap_err("Failed to get sui coins")?;
 
-        gas_coins.sort_unstable_by_key(|c| c.balance);
-        let target_idx = gas_coins
-            .iter()
-            .position(|coin| coin.balance >= amount)
-            .ok_or_else(|| eyre!("There isn't coin with balance higher than provided amount"))?;
-
-        let target = gas_coins.swap_remove(target_idx);
-        Ok((target, gas_coins))
+        select_gas_coins(gas_coins, amount, exclude, self.config.sui.gas_selection)
+            .wrap_err("Failed to select gas coins")
     }
 
     #[instrument(name = "Merging all gas", skip(self))]
     pub async fn merge_all_gas(&mut self) -> Result<(u64, ObjectID)> {
-        let gas_budget = MERGE_SUI_GAS_BUDGET * 2;
-        let (gas_payer, coins_to_merge) = self
-            .find_gas_coin_to_pay_gas_budget(gas_budget)
-            .await
-            .wrap_err("Failed to find suitable gas coin to pay for merging all gas coins")?;
+        self.merge_all_coins(SUI_COIN_TYPE).await
+    }
 
-        let (target, resource) = coins_to_merge
-            .split_first()
-            .ok_or_else(|| eyre!("You don't have enough gas coins for merging. You must have one suitable for paying gas, one target coin and resource coins"))?;
+    /// Merges all coins of `coin_type` owned by active address into one coin and returns its
+    /// balance and id.
+    ///
+    /// SUI coins are merged into the gas coin unless gas is sponsored. Coins are merged with
+    /// `MergeCoins` commands, splitting them into several transactions if there are too many.
+    #[instrument(name = "Merging all coins", skip(self))]
+    pub async fn merge_all_coins(&mut self, coin_type: &str) -> Result<(u64, ObjectID)> {
+        let is_sui = TypeTag::from_str(coin_type).map_err(|e| eyre!(e))?
+            == TypeTag::from_str(SUI_COIN_TYPE).map_err(|e| eyre!(e))?;
+
+        let target = if is_sui && !self.is_sponsored() {
+            self.merge_coins_into_gas().await?
+        } else {
+            self.merge_coins_into_first(coin_type).await?
+        };
 
-        ensure!(
-            !resource.is_empty(),
-            "You must to have at least one resource coin"
-        );
+        let ret = self
+            .get_coins(self.active_address, Some(coin_type.to_string()))
+            .await?
+            .into_iter()
+            .find(|coin| coin.coin_object_id == target)
+            .map(|coin| coin.balance)
+            .ok_or_else(|| eyre!("Coin {target} which coins were merged into is not found"))?;
+
+        info!("Merged coins successfully, total amount is {ret}");
+        Ok((ret, target))
+    }
 
-        let mut ret = target.balance;
-        let mut builder = ProgrammableTransactionBuilder::default();
-        let sui_framework_package = ObjectID::from_hex_literal(
-            "0x0000000000000000000000000000000000000000000000000000000000000002",
-        )
-        .wrap_err("Failed to create object id for sui-framework packages")?;
-        let pay_module = Identifier::from_str("pay")
-            .map_err(|e| eyre!(e))
-            .wrap_err("Failed to create identifier for sui-framework module")?;
-        let join_function = Identifier::from_str("join")
-            .map_err(|e| eyre!(e))
-            .wrap_err("Failed to create identifier for sui-framework function")?;
-        let sui_coin_type_arg = TypeTag::from_str(&gas_payer.coin_type)
-            .map_err(|e| eyre!(e))
-            .wrap_err("Failed to create type tag for SUI")?;
-
-        for coin in resource {
-            let call_args = vec![
-                CallArg::Object(ObjectArg::ImmOrOwnedObject(target.object_ref())),
-                CallArg::Object(ObjectArg::ImmOrOwnedObject(coin.object_ref())),
-            ];
-
-            builder
-                .move_call(
-                    sui_framework_package,
-                    pay_module.clone(),
-                    join_function.clone(),
-                    vec![sui_coin_type_arg.clone()],
-                    call_args,
-                )
-                .map_err(|e| eyre!(e))
-                .wrap_err("Failed to add move call in programmable tx builder")?;
+    async fn merge_coins_into_gas(&mut self) -> Result<ObjectID> {
+        loop {
+            let coins = self
+                .get_sui_coins(self.gas_owner)
+                .await
+                .wrap_err("Failed to get sui coins")?;
+            let gas_budget =
+                merge_coins_gas_budget(coins.len().saturating_sub(1).min(MAX_MERGE_COINS_PER_TX));
+            let (gas_payers, rest) =
+                select_gas_coins(coins, gas_budget, &[], self.config.sui.gas_selection).wrap_err(
+                    "Failed to find suitable gas coin to pay for merging all gas coins",
+                )?;
+            let target = gas_payers
+                .first()
+                .ok_or_else(|| eyre!("Gas payment must contain at least one coin"))?
+                .coin_object_id;
+
+            let mut builder = ProgrammableTransactionBuilder::default();
+            match next_gas_merge_step(gas_payers.len(), rest.len()) {
+                GasMergeStep::Done => return Ok(target),
+                // gas payers are smashed on execution, the gas coin is sent back to its owner
+                GasMergeStep::SmashPayers => {
+                    builder.transfer_arg(self.gas_owner, Argument::GasCoin)
+                }
+                GasMergeStep::Merge(count) => {
+                    add_merge_coins_commands(&mut builder, Argument::GasCoin, &rest[..count])?
+                }
+            }
+            self.execute_merge(builder.finish(), gas_payers, gas_budget)
+                .await?;
+        }
+    }
 
-            ret += coin.balance;
+    async fn merge_coins_into_first(&mut self, coin_type: &str) -> Result<ObjectID> {
+        let mut coins = self
+            .get_coins(self.active_address, Some(coin_type.to_string()))
+            .await?;
+        coins.sort_unstable_by_key(|coin| std::cmp::Reverse(coin.balance));
+        let (target, rest) = coins
+            .split_first()
+            .ok_or_else(|| eyre!("Active address doesn't have coins of type {coin_type}"))?;
+
+        for chunk in rest.chunks(MAX_MERGE_COINS_PER_TX) {
+            let [target_ref] = self
+                .get_objects_references::<1>(vec![target.coin_object_id])
+                .await
+                .wrap_err("Failed to get reference of target coin")?;
+            let exclude: Vec<_> = chunk
+                .iter()
+                .map(|coin| coin.coin_object_id)
+                .chain([target.coin_object_id])
+                .collect();
+            let gas_budget = merge_coins_gas_budget(chunk.len());
+            let (gas_payers, _) = self
+                .find_gas_coins_to_pay_gas_budget(gas_budget, &exclude)
+                .await
+                .wrap_err("Failed to find suitable gas coin to pay for merging coins")?;
+
+            let mut builder = ProgrammableTransactionBuilder::default();
+            let target_arg = builder
+                .obj(ObjectArg::ImmOrOwnedObject(target_ref))
+                .map_err(|e| eyre!(e))
+                .wrap_err("Failed to add target coin in programmable tx builder")?;
+            add_merge_coins_commands(&mut builder, target_arg, chunk)?;
+            self.execute_merge(builder.finish(), gas_payers, gas_budget)
+                .await?;
         }
 
-        let gas_price = self
-            .client
-            .read_api()
-            .get_reference_gas_price()
+        Ok(target.coin_object_id)
+    }
+
+    async fn execute_merge(
+        &mut self,
+        pt: ProgrammableTransaction,
+        gas_payers: Vec<Coin>,
+        gas_budget: u64,
+    ) -> Result<()> {
+        let tx_data = self
+            .programmable_tx_data(pt, object_refs(&gas_payers), gas_budget)
+            .await
+            .wrap_err("Failed to create tx data for merging coins")?;
+
+        let signatures = self
+            .sign(&tx_data)
+            .wrap_err("Failed to sign data for merge tx")?;
+
+        let tx = verify_tx_data(tx_data, signatures)
+            .wrap_err("Failed to verify tx data for merging coins")?;
+
+        self.execute_tx("merge_coins", tx)
             .await
-            .wrap_err("Failed to get gas price")?;
+            .wrap_err("Failed to execute tx with coins merging")?
+            .try_into_effects()?
+            .assert_success()
+            .wrap_err("Failed to merge coins")?;
+
+        Ok(())
+    }
+
+    /// Splits gas owner's coins into new coins with provided `amounts`.
+    ///
+    /// The transaction is sent by gas owner itself, so it works with sponsored gas as well.
+    #[instrument(name = "Splitting gas coin", skip(self))]
+    pub async fn split_gas_coin(&mut self, amounts: Vec<u64>) -> Result<Vec<ObjectID>> {
+        ensure!(!amounts.is_empty(), "You must provide at least one amount");
+        let (gas_payers, _) = self
+            .find_gas_coins_to_pay_gas_budget(split_budget(&amounts)?, &[])
+            .await
+            .wrap_err("Failed to find gas coins to split")?;
+
+        self.split_coins_with(gas_payers, amounts).await
+    }
+
+    /// Splits `gas_payers`, smashed into the first of them, into new coins with `amounts`.
+    async fn split_coins_with(
+        &mut self,
+        gas_payers: Vec<Coin>,
+        amounts: Vec<u64>,
+    ) -> Result<Vec<ObjectID>> {
+        let mut builder = ProgrammableTransactionBuilder::default();
+        let amounts = amounts
+            .into_iter()
+            .map(|amount| builder.pure(amount))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| eyre!(e))
+            .wrap_err("Failed to add amounts in programmable tx builder")?;
+        let count = amounts.len();
+        let Argument::Result(split_idx) =
+            builder.command(Command::SplitCoins(Argument::GasCoin, amounts))
+        else {
+            bail!("SplitCoins command must return result argument");
+        };
+        let new_coins = (0..count)
+            .map(|i| Argument::NestedResult(split_idx, i as u16))
+            .collect();
+        builder.transfer_args(self.gas_owner, new_coins);
         let pt = builder.finish();
 
+        let gas_price = self.reference_gas_price().await?;
         let tx_data = TransactionData::new_programmable(
-            self.active_address,
-            vec![gas_payer.object_ref()],
+            self.gas_owner,
+            object_refs(&gas_payers),
             pt,
-            gas_budget,
+            SPLIT_COIN_GAS_BUDGET,
             gas_price,
         );
-
         let signature = self
-            .sign(&tx_data)
-            .wrap_err("Failed to sign data for merge tx")?;
-
-        let tx = verify_tx_data(tx_data, signature)
-            .wrap_err("Failed to verify tx data for merging gas")?;
+            .keystore
+            .sign_secure(&self.gas_owner, &tx_data, Intent::sui_transaction())
+            .wrap_err("Failed to sign data for split tx")?;
+        let tx = verify_tx_data(tx_data, vec![signature])
+            .wrap_err("Failed to verify tx data for splitting gas")?;
 
-        self.execute_tx(tx)
+        let effects = self
+            .execute_tx("split_gas_coin", tx)
             .await
-            .wrap_err("Failed to execute tx with gas merging")?
+            .wrap_err("Failed to execute tx with gas splitting")?
             .try_into_effects()?
             .assert_success()
-            .wrap_err("Failed to merge gas")?;
+            .wrap_err("Failed to split gas")?;
 
-        info!("Merged gas successfully, total amount is {ret}");
-        Ok((ret, target.coin_object_id))
+        let ret: Vec<_> = effects
+            .created
+            .into_iter()
+            .map(|OwnedObjectRef { reference, .. }| reference.object_id)
+            .collect();
+        info!("Split gas into {} new coins", ret.len());
+
+        Ok(ret)
     }
 
-    #[instrument(name = "Getting SUI objects", skip(self))]
-    async fn get_sui_coins(&self) -> Result<Vec<Coin>> {
-        let ret = self
-            .client
-            .coin_read_api()
-            .get_coins(self.active_address, None, None, None)
+    /// Makes sure gas owner has at least `count` coins with balance of at least `amount`, so
+    /// `count` transactions can be executed in parallel, each with its own gas coin.
+    #[instrument(name = "Ensuring gas coins", skip(self))]
+    pub async fn ensure_gas_coins(&mut self, count: usize, amount: u64) -> Result<Vec<Coin>> {
+        let usable = |coins: Vec<Coin>| -> Vec<Coin> {
+            coins
+                .into_iter()
+                .filter(|coin| coin.balance >= amount)
+                .collect()
+        };
+
+        let coins = self.get_sui_coins(self.gas_owner).await?;
+        let usable_count = coins.iter().filter(|coin| coin.balance >= amount).count();
+        if usable_count >= count {
+            return Ok(usable(coins));
+        }
+
+        // gas payers are smashed into the first one, so usable payers besides it are gone after
+        // split, and the first one stays usable only if enough is left after paying for split
+        let mut missing = count - usable_count;
+        let gas_payers = loop {
+            let budget = split_budget(&vec![amount; missing])?;
+            let (gas_payers, rest) =
+                select_gas_coins(coins.clone(), budget, &[], self.config.sui.gas_selection)
+                    .wrap_err("Failed to select gas coins to split")?;
+            let smashed: u128 = gas_payers.iter().map(|coin| coin.balance as u128).sum();
+            let payer_remains = smashed - budget as u128 >= amount as u128;
+            let remaining = rest.iter().filter(|coin| coin.balance >= amount).count()
+                + usize::from(payer_remains);
+            if remaining + missing >= count {
+                break gas_payers;
+            }
+            missing = count - remaining;
+        };
+
+        self.split_coins_with(gas_payers, vec![amount; missing])
             .await
-            .wrap_err("Failed to fetch SUI objects")?
-            .data;
+            .wrap_err("Failed to split gas coins")?;
+
+        let ret = usable(self.get_sui_coins(self.gas_owner).await?);
+        ensure!(
+            ret.len() >= count,
+            "Only {} usable gas coins are available after splitting, but {count} required",
+            ret.len()
+        );
 
         Ok(ret)
     }
 
+    #[instrument(name = "Getting SUI objects", skip(self))]
+    async fn get_sui_coins(&self, owner: SuiAddress) -> Result<Vec<Coin>> {
+        self.get_coins(owner, None).await
+    }
+
+    /// Fetches all pages of `owner`'s coins, SUI coins if `coin_type` isn't provided.
+    #[instrument(name = "Getting coins", skip(self))]
+    async fn get_coins(&self, owner: SuiAddress, coin_type: Option<String>) -> Result<Vec<Coin>> {
+        let mut ret = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self
+                .client
+                .coin_read_api()
+                .get_coins(owner, coin_type.clone(), cursor, None)
+                .await
+                .wrap_err("Failed to fetch coin objects")?;
+            ret.extend(page.data);
+
+            if !page.has_next_page {
+                return Ok(ret);
+            }
+            cursor = page.next_cursor;
+        }
+    }
+
+    /// Requests gas from configured faucet until gas owner's balance covers `required`, waiting
+    /// for received coins to become available after every request.
+    #[instrument(name = "Funding from faucet", skip(self))]
+    pub async fn fund_from_faucet(&self, required: u64) -> Result<()> {
+        let faucet = self
+            .config
+            .faucet
+            .as_ref()
+            .ok_or_else(|| eyre!("Faucet isn't configured"))?;
+
+        for _ in 0..faucet.max_requests {
+            let balance = self.sui_balance(self.gas_owner).await?.total_balance;
+            if balance >= required as u128 {
+                info!("Balance {balance} is enough, faucet isn't needed");
+                return Ok(());
+            }
+
+            let received = faucet::request_gas(&faucet.url, self.gas_owner)
+                .await
+                .wrap_err("Failed to request gas from faucet")?;
+            self.wait_for_coins(&received, faucet.timeout_secs).await?;
+            info!("Received {} coins from faucet", received.len());
+        }
+
+        let balance = self.sui_balance(self.gas_owner).await?.total_balance;
+        ensure!(
+            balance >= required as u128,
+            "Balance {balance} is still lower than required {required} after {} faucet requests",
+            faucet.max_requests
+        );
+
+        Ok(())
+    }
+
+    async fn wait_for_coins(&self, ids: &[ObjectID], timeout_secs: u64) -> Result<()> {
+        let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+        loop {
+            let coins = self.get_sui_coins(self.gas_owner).await?;
+            let arrived = ids
+                .iter()
+                .all(|id| coins.iter().any(|coin| coin.coin_object_id == *id));
+            if arrived {
+                return Ok(());
+            }
+
+            ensure!(
+                Instant::now() < deadline,
+                "Coins from faucet didn't arrive in {timeout_secs} seconds"
+            );
+            tokio::time::sleep(Duration::from_millis(FAUCET_POLL_INTERVAL_MS)).await;
+        }
+    }
+
     #[instrument(name = "Getting SUI balance", skip(self))]
     pub async fn sui_balance(&self, address: SuiAddress) -> Result<Balance> {
         self.client
@@ -188,61 +446,94 @@ impl Deployer {
             .wrap_err_with(|| format!("Failed to get SUI balance for address: {address}"))
     }
 
-    #[instrument(name = "Publishing package", skip(self))]
+    #[instrument(name = "Publishing package", skip_all)]
     pub async fn publish_package(
         &mut self,
-        package_path: &Path,
+        published_dependencies: PublishedDependencies,
+        compiled_modules: CompiledModules,
     ) -> Result<SuiTransactionBlockResponse> {
-        let (gas_payer, _) = self
-            .find_gas_coin_to_pay_gas_budget(PUBLISH_PACKAGE_GAS_BUDGET)
+        let (gas_payers, _) = self
+            .find_gas_coins_to_pay_gas_budget(PUBLISH_PACKAGE_GAS_BUDGET, &[])
             .await
             .wrap_err("Failed to update gas for publishing package")?;
 
-        let (published_dependencies, compiled_modules) = build_and_compile_package(package_path)?;
+        let mut builder = ProgrammableTransactionBuilder::default();
+        let upgrade_cap = builder.publish_upgradeable(compiled_modules, published_dependencies);
+        builder.transfer_arg(self.active_address, upgrade_cap);
+        let pt = builder.finish();
 
         let tx_data = self
-            .client
-            .transaction_builder()
-            .publish(
-                self.active_address,
-                compiled_modules,
-                published_dependencies,
-                Some(gas_payer.coin_object_id),
-                PUBLISH_PACKAGE_GAS_BUDGET,
-            )
+            .programmable_tx_data(pt, object_refs(&gas_payers), PUBLISH_PACKAGE_GAS_BUDGET)
             .await
-            .map_err(|e| eyre!(e))
             .wrap_err("Failed to build transaction for publishing package")?;
 
-        let signature = self
+        let signatures = self
             .sign(&tx_data)
             .wrap_err("Failed to sign data for publish tx")?;
 
-        let tx = verify_tx_data(tx_data, signature)
+        let tx = verify_tx_data(tx_data, signatures)
             .wrap_err("Failed to verify tx data for publishing package")?;
 
         let ret = self
-            .execute_tx(tx)
+            .execute_tx("publish_package", tx)
             .await
             .wrap_err("Failed to execute tx with package publishing")?;
 
         Ok(ret)
     }
 
-    #[instrument(name = "Executing transaction", skip_all)]
+    #[instrument(
+        name = "Executing transaction",
+        skip(self, tx),
+        fields(
+            tx_digest = field::Empty,
+            computation_cost = field::Empty,
+            storage_cost = field::Empty,
+            storage_rebate = field::Empty,
+        )
+    )]
     async fn execute_tx(
-        &self,
+        &mut self,
+        step: &str,
         tx: VerifiedEnvelope<SenderSignedData, EmptySignInfo>,
     ) -> Result<SuiTransactionBlockResponse> {
-        self.client
+        let ret = self
+            .client
             .quorum_driver()
             .execute_transaction_block(
                 tx,
-                SuiTransactionBlockResponseOptions::new().with_effects(),
+                SuiTransactionBlockResponseOptions::new()
+                    .with_effects()
+                    .with_events(),
                 Some(sui_types::messages::ExecuteTransactionRequestType::WaitForLocalExecution),
             )
             .await
-            .wrap_err("Failed to execute tx")
+            .wrap_err("Failed to execute tx")?;
+
+        let span = Span::current();
+        span.record("tx_digest", field::display(ret.digest));
+        if let Some(SuiTransactionBlockEffects::V1(effects)) = &ret.effects {
+            span.record("computation_cost", effects.gas_used.computation_cost);
+            span.record("storage_cost", effects.gas_used.storage_cost);
+            span.record("storage_rebate", effects.gas_used.storage_rebate);
+            self.gas_report.record(step, effects);
+        }
+        if let Some(events) = &ret.events {
+            for event in &events.data {
+                info!("Event {} in {}", event.type_, event.id.tx_digest);
+                self.events.push((step.to_string(), event.clone()));
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Records of captured events, classified against the published `package`.
+    pub fn event_records(&self, package: ObjectID) -> Vec<EventRecord> {
+        self.events
+            .iter()
+            .map(|(step, event)| EventRecord::new(step, event, package))
+            .collect()
     }
 
     #[instrument(name = "Processing publish effects", skip_all)]
@@ -268,7 +559,7 @@ impl Deployer {
                     })
             };
 
-            tasks.push(tokio::spawn(task));
+            tasks.push(tokio::spawn(task.in_current_span()));
         }
         let mut ret = Vec::new();
         for task in tasks {
@@ -281,8 +572,8 @@ impl Deployer {
 
     #[instrument(name = "Setting up package", skip_all)]
     pub async fn setup_package(&mut self, publish_result: PublishResult) -> Result<()> {
-        let (gas_payer, _) = self
-            .find_gas_coin_to_pay_gas_budget(SETUP_PACKAGE_GAS_BUDGET)
+        let (gas_payers, _) = self
+            .find_gas_coins_to_pay_gas_budget(SETUP_PACKAGE_GAS_BUDGET, &[])
             .await
             .wrap_err("Failed to find gas coin to setup package")?;
 
@@ -329,21 +620,11 @@ impl Deployer {
             .map_err(|e| eyre!(e))
             .wrap_err("Failed to add move call in programmable tx builder")?;
 
-        let gas_price = self
-            .client
-            .read_api()
-            .get_reference_gas_price()
-            .await
-            .wrap_err("Failed to get gas price")?;
         let pt = builder.finish();
-
-        let tx_data = TransactionData::new_programmable(
-            self.active_address,
-            vec![gas_payer.object_ref()],
-            pt,
-            SETUP_PACKAGE_GAS_BUDGET,
-            gas_price,
-        );
+        let tx_data = self
+            .programmable_tx_data(pt, object_refs(&gas_payers), SETUP_PACKAGE_GAS_BUDGET)
+            .await
+            .wrap_err("Failed to create tx data to setup package")?;
 
         // let tx_data = self
         //     .client
@@ -373,28 +654,143 @@ impl Deployer {
         // .map_err(|e| eyre!(e))
         // .wrap_err("Failed to build transaction to setup package")?;
 
-        let signature = self
+        let signatures = self
             .sign(&tx_data)
             .wrap_err("Failed to sign data to setup package")?;
 
-        let tx = verify_tx_data(tx_data, signature)
+        let tx = verify_tx_data(tx_data, signatures)
             .wrap_err("Failed to verify tx data to setup package")?;
 
-        self.execute_tx(tx)
+        self.execute_tx("setup_package", tx)
             .await
-            .wrap_err("Failed to execute tx with package setup")?;
+            .wrap_err("Failed to execute tx with package setup")?
+            .try_into_effects()?
+            .assert_success()
+            .wrap_err("Failed to setup package")?;
 
         Ok(())
     }
 
+    /// Transfers caps to their new `owners`, see `SuiConfig::cap_owners`, in one transaction.
+    #[instrument(name = "Transferring caps", skip_all)]
+    pub async fn transfer_caps(
+        &mut self,
+        publish_result: &PublishResult,
+        owners: &BTreeMap<String, SuiAddress>,
+    ) -> Result<()> {
+        if owners.is_empty() {
+            return Ok(());
+        }
+
+        let entries: BTreeMap<_, _> = publish_result.entries()?.into_iter().collect();
+        let mut recipients: BTreeMap<SuiAddress, Vec<ObjectID>> = BTreeMap::new();
+        for (cap, owner) in owners {
+            let id = entries
+                .get(cap)
+                .ok_or_else(|| eyre!("Cap `{cap}` isn't found in publish result"))?;
+            recipients.entry(*owner).or_default().push(*id);
+        }
+
+        let (gas_payers, _) = self
+            .find_gas_coins_to_pay_gas_budget(TRANSFER_CAPS_GAS_BUDGET, &[])
+            .await
+            .wrap_err("Failed to find gas coin to transfer caps")?;
+
+        let mut builder = ProgrammableTransactionBuilder::default();
+        for (recipient, caps) in recipients {
+            let mut args = Vec::new();
+            for cap in caps {
+                let cap_ref = self
+                    .client
+                    .read_api()
+                    .get_object_with_options(cap, SuiObjectDataOptions::default())
+                    .await
+                    .wrap_err_with(|| format!("Failed to get object with id {cap}"))?
+                    .object_ref_if_exists()
+                    .ok_or_else(|| eyre!("Cap {cap} doesn't exist"))?;
+                let arg = builder
+                    .obj(ObjectArg::ImmOrOwnedObject(cap_ref))
+                    .map_err(|e| eyre!(e))
+                    .wrap_err("Failed to add cap into programmable tx builder")?;
+                args.push(arg);
+            }
+            builder.transfer_args(recipient, args);
+        }
+        let pt = builder.finish();
+
+        let tx_data = self
+            .programmable_tx_data(pt, object_refs(&gas_payers), TRANSFER_CAPS_GAS_BUDGET)
+            .await
+            .wrap_err("Failed to create tx data to transfer caps")?;
+
+        let signatures = self
+            .sign(&tx_data)
+            .wrap_err("Failed to sign data to transfer caps")?;
+
+        let tx = verify_tx_data(tx_data, signatures)
+            .wrap_err("Failed to verify tx data to transfer caps")?;
+
+        self.execute_tx("transfer_caps", tx)
+            .await
+            .wrap_err("Failed to execute tx with caps transfer")?
+            .try_into_effects()?
+            .assert_success()?;
+
+        for (cap, owner) in owners {
+            info!("Transferred `{cap}` to {owner}");
+        }
+
+        Ok(())
+    }
+
+    /// Wraps programmable transaction into transaction data with `active_address` as sender
+    /// and `gas_owner` as owner of gas payment.
+    #[instrument(name = "Creating transaction data", skip(self, pt))]
+    async fn programmable_tx_data(
+        &self,
+        pt: ProgrammableTransaction,
+        gas_payment: Vec<ObjectRef>,
+        gas_budget: u64,
+    ) -> Result<TransactionData> {
+        let gas_price = self.reference_gas_price().await?;
+
+        let ret = TransactionData::new_programmable_allow_sponsor(
+            self.active_address,
+            gas_payment,
+            pt,
+            gas_budget,
+            gas_price,
+            self.gas_owner,
+        );
+
+        Ok(ret)
+    }
+
+    async fn reference_gas_price(&self) -> Result<u64> {
+        self.client
+            .read_api()
+            .get_reference_gas_price()
+            .await
+            .wrap_err("Failed to get gas price")
+    }
+
+    /// Signs transaction data by sender and, if gas is sponsored, by gas owner.
     #[instrument(name = "Signing transaction data", skip_all)]
-    fn sign(&self, data: &TransactionData) -> Result<Signature> {
-        let signature = self
+    fn sign(&self, data: &TransactionData) -> Result<Vec<Signature>> {
+        let mut ret = vec![self
             .keystore
             .sign_secure(&self.active_address, data, Intent::sui_transaction())
-            .wrap_err("Failed to sign tx data")?;
+            .wrap_err("Failed to sign tx data")?];
+
+        if self.is_sponsored() {
+            let sponsor_signature = self
+                .keystore
+                .sign_secure(&self.gas_owner, data, Intent::sui_transaction())
+                .wrap_err("Failed to sign tx data by sponsor")?;
+            ret.push(sponsor_signature);
+        }
 
-        Ok(signature)
+        Ok(ret)
     }
 
     #[instrument(name = "Getting objects references", skip(self))]
@@ -415,7 +811,7 @@ impl Deployer {
                     .wrap_err_with(|| format!("Failed to get object with id {}", object_id))
             };
 
-            tasks.push(tokio::spawn(task));
+            tasks.push(tokio::spawn(task.in_current_span()));
         }
         let mut ret = Vec::new();
         for task in tasks {
@@ -427,15 +823,154 @@ impl Deployer {
     }
 }
 
-type CompiledModules = Vec<Vec<u8>>;
-type PublishedDependencies = Vec<ObjectID>;
+/// Gas budget of splitting coins into `amounts`, including the split itself.
+fn split_budget(amounts: &[u64]) -> Result<u64> {
+    amounts
+        .iter()
+        .try_fold(SPLIT_COIN_GAS_BUDGET, |acc, amount| {
+            acc.checked_add(*amount)
+        })
+        .ok_or_else(|| eyre!("Total amount to split doesn't fit into u64"))
+}
+
+/// Returns active address and gas owner from config, making sure keystore contains their keys.
+pub fn resolve_addresses(
+    keystore: &Keystore,
+    config: &SuiConfig,
+) -> Result<(SuiAddress, SuiAddress)> {
+    let active_address = match config.active_address.as_deref() {
+        None => *keystore
+            .addresses()
+            .last()
+            .ok_or_else(|| eyre!("Keystore doesn't contain any keys"))?,
+        Some(address) => {
+            let address = SuiAddress::from_str(address)
+                .map_err(|e| eyre!(e))
+                .wrap_err("Failed to parse active address")?;
+            ensure!(
+                keystore.addresses().contains(&address),
+                "Active address {address} isn't present in keystore"
+            );
+            address
+        }
+    };
+
+    let gas_owner = match config.sponsor_address.as_deref() {
+        None => active_address,
+        Some(sponsor) => {
+            let sponsor = SuiAddress::from_str(sponsor)
+                .map_err(|e| eyre!(e))
+                .wrap_err("Failed to parse sponsor address")?;
+            ensure!(
+                keystore.addresses().contains(&sponsor),
+                "Sponsor address {sponsor} isn't present in keystore"
+            );
+            sponsor
+        }
+    };
+
+    Ok((active_address, gas_owner))
+}
+
+/// Gas budget of a transaction merging `coins` coins.
+fn merge_coins_gas_budget(coins: usize) -> u64 {
+    MERGE_COINS_GAS_BUDGET + MERGE_COINS_GAS_BUDGET_PER_COIN * coins as u64
+}
+
+#[derive(Debug, PartialEq)]
+enum GasMergeStep {
+    /// Gas is a single coin already.
+    Done,
+    /// Only coins paying for gas are left, they are smashed into one by any transaction.
+    SmashPayers,
+    /// Merge this many of the rest of coins into gas coin.
+    Merge(usize),
+}
+
+/// What is left to merge into gas coin, given numbers of coins selected for gas payment and of
+/// the rest of coins.
+fn next_gas_merge_step(gas_payers: usize, rest: usize) -> GasMergeStep {
+    if rest > 0 {
+        GasMergeStep::Merge(rest.min(MAX_MERGE_COINS_PER_TX))
+    } else if gas_payers > 1 {
+        GasMergeStep::SmashPayers
+    } else {
+        GasMergeStep::Done
+    }
+}
+
+fn add_merge_coins_commands(
+    builder: &mut ProgrammableTransactionBuilder,
+    target: Argument,
+    coins: &[Coin],
+) -> Result<()> {
+    for chunk in coins.chunks(MAX_MERGE_COINS_PER_COMMAND) {
+        let coins = chunk
+            .iter()
+            .map(|coin| builder.obj(ObjectArg::ImmOrOwnedObject(coin.object_ref())))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| eyre!(e))
+            .wrap_err("Failed to add coins in programmable tx builder")?;
+        builder.command(Command::MergeCoins(target, coins));
+    }
 
-#[instrument(name = "Building and compiling package")]
-fn build_and_compile_package(
+    Ok(())
+}
+
+fn object_refs(coins: &[Coin]) -> Vec<ObjectRef> {
+    coins.iter().map(Coin::object_ref).collect()
+}
+
+pub type CompiledModules = Vec<Vec<u8>>;
+pub type PublishedDependencies = Vec<ObjectID>;
+
+/// Hash of compiled bytecode of packages and published dependencies, identifies package contents
+/// between runs.
+pub fn package_digest(dependencies: &[ObjectID], packages: &[CompiledModules]) -> String {
+    let mut dependencies = dependencies.to_vec();
+    dependencies.sort();
+
+    let mut hasher = Sha256::new();
+    for id in dependencies {
+        hasher.update(id.as_ref());
+    }
+    for modules in packages {
+        hasher.update((modules.len() as u64).to_le_bytes());
+        for module in modules {
+            hasher.update((module.len() as u64).to_le_bytes());
+            hasher.update(module);
+        }
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Builds package, verifies its bytecode and reports its modules, so a malformed package fails
+/// before anything is sent to the network.
+#[instrument(name = "Building and compiling package", skip(options))]
+pub fn build_and_compile_package(
     package_path: &Path,
+    options: &BuildOptions,
 ) -> Result<(PublishedDependencies, CompiledModules)> {
-    let package = build_move_package(package_path, Default::default())
-        .wrap_err("Failed to build move package")?;
+    let mut build_config = BuildConfig::default();
+    build_config.config.dev_mode = options.dev;
+    build_config.config.test_mode = options.test;
+    build_config.config.skip_fetch_latest_git_deps = options.skip_fetch_latest_git_deps;
+    build_config.config.silence_warnings = !options.lint;
+    build_config.run_bytecode_verifier = true;
+    build_config.print_diags_to_stderr = true;
+    for (name, address) in &options.named_addresses {
+        let address = AccountAddress::from_hex_literal(address)
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| format!("Failed to parse named address `{name}`"))?;
+        build_config
+            .config
+            .additional_named_addresses
+            .insert(name.clone(), address);
+    }
+
+    let package =
+        build_move_package(package_path, build_config).wrap_err("Failed to build move package")?;
     let dependencies: Vec<_> = package
         .dependency_ids
         .published
@@ -443,7 +978,31 @@ fn build_and_compile_package(
         .into_values()
         .collect();
 
-    let modules = package.get_package_bytes(true);
+    let mut modules = Vec::new();
+    let mut total_size = 0;
+    for module in package.get_dependency_sorted_modules(options.with_unpublished_dependencies) {
+        let mut bytes = Vec::new();
+        module
+            .serialize(&mut bytes)
+            .map_err(|e| eyre!(e))
+            .wrap_err("Failed to serialize module")?;
+        info!("Module {}: {} bytes", module.self_id().name(), bytes.len());
+        total_size += bytes.len();
+        modules.push(bytes);
+    }
+
+    let max_package_size = ProtocolConfig::get_for_max_version().max_move_package_size();
+    info!(
+        "Package contains {} modules, {total_size} of {max_package_size} bytes allowed",
+        modules.len()
+    );
+    for dependency in &dependencies {
+        info!("Depends on published package {dependency}");
+    }
+    ensure!(
+        total_size as u64 <= max_package_size,
+        "Package size {total_size} exceeds maximum of {max_package_size} bytes"
+    );
 
     Ok((dependencies, modules))
 }
@@ -451,9 +1010,39 @@ fn build_and_compile_package(
 #[instrument(name = "Verifying transaction data", skip_all)]
 fn verify_tx_data(
     tx_data: TransactionData,
-    signature: Signature,
+    signatures: Vec<Signature>,
 ) -> Result<VerifiedEnvelope<SenderSignedData, EmptySignInfo>> {
-    Transaction::from_data(tx_data, Intent::sui_transaction(), vec![signature])
+    Transaction::from_data(tx_data, Intent::sui_transaction(), signatures)
         .verify()
         .wrap_err("Failed to verify tx")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gas_merge_is_done_for_single_coin() {
+        assert_eq!(next_gas_merge_step(1, 0), GasMergeStep::Done);
+    }
+
+    #[test]
+    fn gas_payers_are_smashed_when_nothing_else_is_left() {
+        assert_eq!(next_gas_merge_step(3, 0), GasMergeStep::SmashPayers);
+    }
+
+    #[test]
+    fn rest_of_coins_is_merged_in_chunks() {
+        assert_eq!(next_gas_merge_step(2, 10), GasMergeStep::Merge(10));
+        assert_eq!(
+            next_gas_merge_step(1, MAX_MERGE_COINS_PER_TX * 2),
+            GasMergeStep::Merge(MAX_MERGE_COINS_PER_TX)
+        );
+    }
+
+    #[test]
+    fn merge_gas_budget_grows_with_coins() {
+        assert_eq!(merge_coins_gas_budget(0), MERGE_COINS_GAS_BUDGET);
+        assert!(merge_coins_gas_budget(MAX_MERGE_COINS_PER_TX) > merge_coins_gas_budget(10));
+    }
+}
@@ -0,0 +1,136 @@
+use eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use sui_types::base_types::{ObjectID, SuiAddress};
+
+use crate::constants::DEPLOYMENTS_DIR;
+use crate::events::EventRecord;
+use crate::gas_report::GasReport;
+use crate::publish_result::PublishResult;
+
+/// Everything we know about a deployment: created objects and what it cost.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentRecord {
+    #[serde(flatten)]
+    pub publish_result: PublishResult,
+    /// Packages published before the main one, by name from config.
+    #[serde(default)]
+    pub packages: BTreeMap<String, ObjectID>,
+    pub gas_report: GasReport,
+    #[serde(default)]
+    pub events: Vec<EventRecord>,
+    /// New owners of caps transferred after setup, by field of publish result.
+    #[serde(default)]
+    pub cap_owners: BTreeMap<String, SuiAddress>,
+    pub chain_id: String,
+    /// Digest of published bytecode, see `deployer::package_digest`.
+    pub package_digest: String,
+}
+
+impl DeploymentRecord {
+    /// Path of the record of the last deployment to network with `chain_id`, `base_dir` is the
+    /// directory of config file.
+    pub fn path(base_dir: &Path, chain_id: &str) -> PathBuf {
+        base_dir
+            .join(DEPLOYMENTS_DIR)
+            .join(format!("{chain_id}.json"))
+    }
+
+    /// Record of the last deployment to network with `chain_id`, if there was one.
+    pub fn last(base_dir: &Path, chain_id: &str) -> Result<Option<Self>> {
+        let path = Self::path(base_dir, chain_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Self::from_file(&path).map(Some)
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).wrap_err("Failed to create directory")?;
+        }
+        let file = File::create(path).wrap_err("Failed to create file")?;
+        serde_json::to_writer_pretty(file, self).wrap_err("Failed to serialize data into file")
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).wrap_err("Failed to open file with deployment record")?;
+        serde_json::from_reader(file).wrap_err("Failed to deserialize file into struct")
+    }
+
+    /// Caps from `owners` which aren't transferred yet, e.g. because transfer failed or owners
+    /// were configured after deployment.
+    pub fn pending_cap_owners(
+        &self,
+        owners: &BTreeMap<String, SuiAddress>,
+    ) -> Result<BTreeMap<String, SuiAddress>> {
+        pending_cap_owners(&self.cap_owners, owners)
+    }
+}
+
+fn pending_cap_owners(
+    transferred: &BTreeMap<String, SuiAddress>,
+    owners: &BTreeMap<String, SuiAddress>,
+) -> Result<BTreeMap<String, SuiAddress>> {
+    let mut ret = BTreeMap::new();
+    for (cap, owner) in owners {
+        match transferred.get(cap) {
+            None => {
+                ret.insert(cap.clone(), *owner);
+            }
+            Some(transferred) if transferred == owner => {}
+            Some(transferred) => {
+                bail!("Cap `{cap}` is already transferred to {transferred}, not to {owner}")
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_which_are_not_transferred_are_pending() {
+        let owner = SuiAddress::random_for_testing_only();
+        let transferred = BTreeMap::from([("lemon_cap".to_string(), owner)]);
+        let owners = BTreeMap::from([
+            ("lemon_cap".to_string(), owner),
+            ("juice_cap".to_string(), owner),
+        ]);
+
+        let pending = pending_cap_owners(&transferred, &owners).unwrap();
+
+        assert_eq!(pending, BTreeMap::from([("juice_cap".to_string(), owner)]));
+    }
+
+    #[test]
+    fn nothing_is_pending_when_all_caps_are_transferred() {
+        let owners = BTreeMap::from([(
+            "lemon_cap".to_string(),
+            SuiAddress::random_for_testing_only(),
+        )]);
+
+        assert!(pending_cap_owners(&owners, &owners).unwrap().is_empty());
+    }
+
+    #[test]
+    fn cap_transferred_to_another_owner_is_rejected() {
+        let transferred = BTreeMap::from([(
+            "lemon_cap".to_string(),
+            SuiAddress::random_for_testing_only(),
+        )]);
+        let owners = BTreeMap::from([(
+            "lemon_cap".to_string(),
+            SuiAddress::random_for_testing_only(),
+        )]);
+
+        assert!(pending_cap_owners(&transferred, &owners).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+use std::str::FromStr;
+
+use eyre::{Result, WrapErr};
+use serde::Serialize;
+use serde_json::Value;
+use sui_sdk::rpc_types::{DynamicFieldPage, SuiParsedData};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::dynamic_field::{DynamicFieldInfo, DynamicFieldName, DynamicFieldType};
+use tracing::instrument;
+
+use crate::chain_client::ChainClient;
+use crate::inspect::{fetch_object, resolve_object_id};
+
+/// Dynamic field with its value fetched and decoded.
+#[derive(Debug, Serialize)]
+pub struct DynamicFieldEntry {
+    pub name: DynamicFieldName,
+    /// Id of `Field<Name, Value>` object, or of the child object for dynamic object fields.
+    pub object_id: ObjectID,
+    pub is_object: bool,
+    pub value_type: String,
+    pub value: DecodedValue,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum DecodedValue {
+    Bool(bool),
+    Number(u128),
+    Address(SuiAddress),
+    Id(ObjectID),
+    String(String),
+    /// Any other Move struct, fields as returned by node.
+    Struct(Value),
+    /// Value which doesn't match its declared type.
+    Raw(Value),
+}
+
+#[derive(Debug, Serialize)]
+pub struct DynamicFieldsPage {
+    pub entries: Vec<DynamicFieldEntry>,
+    pub next_cursor: Option<ObjectID>,
+    pub has_next_page: bool,
+}
+
+impl ChainClient {
+    #[instrument(name = "Getting dynamic fields page", skip(self))]
+    pub async fn get_dynamic_fields(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<DynamicFieldPage> {
+        self.client
+            .read_api()
+            .get_dynamic_fields(parent, cursor, limit)
+            .await
+            .wrap_err_with(|| format!("Failed to get dynamic fields of object {parent}"))
+    }
+
+    /// Fetches all pages of dynamic fields of `parent`.
+    #[instrument(name = "Getting all dynamic fields", skip(self))]
+    pub async fn get_all_dynamic_fields(&self, parent: ObjectID) -> Result<Vec<DynamicFieldInfo>> {
+        let mut ret = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self.get_dynamic_fields(parent, cursor, None).await?;
+            ret.extend(page.data);
+
+            if !page.has_next_page {
+                return Ok(ret);
+            }
+            cursor = page.next_cursor;
+        }
+    }
+
+    /// Page of dynamic fields of `parent` with decoded values.
+    #[instrument(name = "Enumerating dynamic fields", skip(self))]
+    pub async fn dynamic_fields_page(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<DynamicFieldsPage> {
+        let page = self.get_dynamic_fields(parent, cursor, limit).await?;
+
+        let mut entries = Vec::new();
+        for info in page.data {
+            let entry = self
+                .decode_dynamic_field(info)
+                .await
+                .wrap_err("Failed to decode dynamic field")?;
+            entries.push(entry);
+        }
+
+        Ok(DynamicFieldsPage {
+            entries,
+            next_cursor: page.next_cursor,
+            has_next_page: page.has_next_page,
+        })
+    }
+
+    async fn decode_dynamic_field(&self, info: DynamicFieldInfo) -> Result<DynamicFieldEntry> {
+        let object = fetch_object(self, info.object_id).await?;
+        let fields = match object.content {
+            Some(SuiParsedData::MoveObject(content)) => {
+                serde_json::to_value(content.fields).wrap_err("Failed to serialize fields")?
+            }
+            _ => Value::Null,
+        };
+
+        let is_object = matches!(info.type_, DynamicFieldType::DynamicObject);
+        let value = if is_object {
+            // the child object itself is the value
+            DecodedValue::Struct(fields)
+        } else {
+            let value = struct_field(&fields, "value")
+                .cloned()
+                .unwrap_or(Value::Null);
+            decode_value(&info.object_type, value)
+        };
+
+        Ok(DynamicFieldEntry {
+            name: info.name,
+            object_id: info.object_id,
+            is_object,
+            value_type: info.object_type,
+            value,
+        })
+    }
+}
+
+/// Field of Move struct JSON, which is either a plain map or `{ type, fields }`.
+fn struct_field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value
+        .get(name)
+        .or_else(|| value.get("fields").and_then(|fields| fields.get(name)))
+}
+
+fn decode_value(value_type: &str, value: Value) -> DecodedValue {
+    let decoded = match value_type {
+        "bool" => value.as_bool().map(DecodedValue::Bool),
+        "u8" | "u16" | "u32" | "u64" | "u128" => match &value {
+            Value::Number(number) => number.as_u64().map(|n| DecodedValue::Number(n.into())),
+            Value::String(number) => number.parse().ok().map(DecodedValue::Number),
+            _ => None,
+        },
+        "address" => value
+            .as_str()
+            .and_then(|address| SuiAddress::from_str(address).ok())
+            .map(DecodedValue::Address),
+        "0x2::object::ID" => value
+            .as_str()
+            .or_else(|| struct_field(&value, "bytes").and_then(Value::as_str))
+            .and_then(|id| ObjectID::from_str(id).ok())
+            .map(DecodedValue::Id),
+        "0x1::string::String" | "0x1::ascii::String" => {
+            value.as_str().map(|s| DecodedValue::String(s.to_string()))
+        }
+        _ if value.is_object() => Some(DecodedValue::Struct(value.clone())),
+        _ => None,
+    };
+
+    decoded.unwrap_or(DecodedValue::Raw(value))
+}
+
+/// Prints page of dynamic fields of `target`, or all of them if `all` is set, as JSON.
+pub async fn print_dynamic_fields(
+    client: &ChainClient,
+    target: &str,
+    mut cursor: Option<ObjectID>,
+    limit: Option<usize>,
+    all: bool,
+) -> Result<()> {
+    let parent = resolve_object_id(client, target)?;
+    loop {
+        let page = client.dynamic_fields_page(parent, cursor, limit).await?;
+        let json = serde_json::to_string_pretty(&page).wrap_err("Failed to serialize page")?;
+        println!("{json}");
+
+        if !all || !page.has_next_page {
+            return Ok(());
+        }
+        cursor = page.next_cursor;
+    }
+}
@@ -0,0 +1,212 @@
+use std::fs::File;
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use eyre::{ensure, eyre, Result, WrapErr};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sui_keys::keystore::{AccountKeystore, InMemKeystore, Keystore};
+use sui_types::crypto::{EncodeDecodeBase64, SuiKeyPair};
+use tracing::{info, instrument};
+
+use crate::constants::{
+    ENCRYPTED_KEYSTORE_VERSION, KEYSTORE_PASSWORD_ENV, SCRYPT_LOG_N, SCRYPT_P, SCRYPT_R,
+};
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Keystore encrypted with AES-256-GCM, key is derived from password with scrypt.
+///
+/// Plaintext is the content of a regular Sui keystore: JSON array of base64 encoded keys.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncryptedKeystore {
+    pub version: u8,
+    pub kdf: KdfParams,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KdfParams {
+    pub salt: String,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl KdfParams {
+    fn derive_key(&self, password: &str) -> Result<[u8; KEY_LEN]> {
+        let salt = STANDARD
+            .decode(&self.salt)
+            .wrap_err("Failed to decode salt")?;
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, KEY_LEN)
+            .map_err(|e| eyre!(e))
+            .wrap_err("Invalid scrypt params")?;
+        let mut ret = [0u8; KEY_LEN];
+        scrypt::scrypt(password.as_bytes(), &salt, &params, &mut ret)
+            .map_err(|e| eyre!(e))
+            .wrap_err("Failed to derive key from password")?;
+
+        Ok(ret)
+    }
+}
+
+impl EncryptedKeystore {
+    #[instrument(name = "Encrypting keystore", skip_all)]
+    pub fn encrypt(keys: &[String], password: &str) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let kdf = KdfParams {
+            salt: STANDARD.encode(salt),
+            log_n: SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+        };
+        let key = kdf.derive_key(password)?;
+        let plaintext = serde_json::to_vec(keys).wrap_err("Failed to serialize keys")?;
+        let ciphertext = Aes256Gcm::new_from_slice(&key)
+            .map_err(|e| eyre!(e))?
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| eyre!("Failed to encrypt keys"))?;
+
+        Ok(Self {
+            version: ENCRYPTED_KEYSTORE_VERSION,
+            kdf,
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    #[instrument(name = "Decrypting keystore", skip_all)]
+    pub fn decrypt(&self, password: &str) -> Result<Vec<SuiKeyPair>> {
+        ensure!(
+            self.version == ENCRYPTED_KEYSTORE_VERSION,
+            "Unsupported encrypted keystore version: {}",
+            self.version
+        );
+
+        let key = self.kdf.derive_key(password)?;
+        let nonce = STANDARD
+            .decode(&self.nonce)
+            .wrap_err("Failed to decode nonce")?;
+        let ciphertext = STANDARD
+            .decode(&self.ciphertext)
+            .wrap_err("Failed to decode ciphertext")?;
+        let plaintext = Aes256Gcm::new_from_slice(&key)
+            .map_err(|e| eyre!(e))?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| eyre!("Failed to decrypt keystore, password is probably wrong"))?;
+        let keys: Vec<String> =
+            serde_json::from_slice(&plaintext).wrap_err("Failed to deserialize keys")?;
+
+        keys.iter()
+            .map(|key| {
+                SuiKeyPair::decode_base64(key)
+                    .map_err(|e| eyre!(e))
+                    .wrap_err("Failed to decode private key")
+            })
+            .collect()
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).wrap_err("Failed to create file")?;
+        serde_json::to_writer_pretty(file, self).wrap_err("Failed to serialize data into file")
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).wrap_err("Failed to open encrypted keystore file")?;
+        serde_json::from_reader(file).wrap_err("Failed to deserialize encrypted keystore")
+    }
+}
+
+/// Decrypts keystore at `path` into in-memory keystore.
+pub fn unlock_keystore(path: &Path, password_var: Option<&str>) -> Result<Keystore> {
+    let password = read_password(password_var, false)?;
+    decrypt_keystore(path, &password)
+}
+
+/// Decrypts keystore at `path`, applies `f` to it and writes it back encrypted with the same
+/// password.
+pub fn update_keystore<T>(
+    path: &Path,
+    password_var: Option<&str>,
+    f: impl FnOnce(&mut Keystore) -> Result<T>,
+) -> Result<T> {
+    let password = read_password(password_var, false)?;
+    let mut keystore = decrypt_keystore(path, &password)?;
+    let ret = f(&mut keystore)?;
+
+    let keys = keystore
+        .addresses()
+        .iter()
+        .map(|address| {
+            keystore
+                .get_key(address)
+                .map(|key| key.encode_base64())
+                .map_err(|e| eyre!(e))
+        })
+        .collect::<Result<Vec<_>>>()
+        .wrap_err("Failed to export keys from keystore")?;
+    EncryptedKeystore::encrypt(&keys, &password)?.to_file(path)?;
+
+    Ok(ret)
+}
+
+fn decrypt_keystore(path: &Path, password: &str) -> Result<Keystore> {
+    let keys = EncryptedKeystore::from_file(path)?.decrypt(password)?;
+
+    let mut ret = InMemKeystore::default();
+    for key in keys {
+        ret.add_key(key)
+            .map_err(|e| eyre!(e))
+            .wrap_err("Failed to add private key into keystore")?;
+    }
+
+    Ok(Keystore::InMem(ret))
+}
+
+/// Converts plaintext Sui keystore at `input` into encrypted keystore at `output`.
+#[instrument(name = "Converting keystore")]
+pub fn convert_keystore(input: &Path, output: &Path) -> Result<()> {
+    let file = File::open(input).wrap_err("Failed to open keystore file")?;
+    let keys: Vec<String> =
+        serde_json::from_reader(file).wrap_err("Failed to deserialize keystore")?;
+    for key in &keys {
+        SuiKeyPair::decode_base64(key)
+            .map_err(|e| eyre!(e))
+            .wrap_err("Keystore contains invalid private key")?;
+    }
+
+    let password = read_password(None, true)?;
+    EncryptedKeystore::encrypt(&keys, &password)?.to_file(output)?;
+    info!("Encrypted {} key(s) into {}", keys.len(), output.display());
+
+    Ok(())
+}
+
+/// Reads password from env var or, if it isn't set, prompts for it.
+fn read_password(password_var: Option<&str>, confirm: bool) -> Result<String> {
+    let var = password_var.unwrap_or(KEYSTORE_PASSWORD_ENV);
+    if let Ok(ret) = std::env::var(var) {
+        return Ok(ret);
+    }
+
+    let ret =
+        rpassword::prompt_password("Keystore password: ").wrap_err("Failed to read password")?;
+    if confirm {
+        let confirmation =
+            rpassword::prompt_password("Repeat password: ").wrap_err("Failed to read password")?;
+        ensure!(ret == confirmation, "Passwords don't match");
+    }
+
+    Ok(ret)
+}
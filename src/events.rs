@@ -0,0 +1,229 @@
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sui_sdk::rpc_types::SuiEvent;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::SUI_FRAMEWORK_ADDRESS;
+
+/// Event emitted by a deployment transaction or by the deployed package afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub step: String,
+    pub tx_digest: String,
+    pub sender: SuiAddress,
+    /// Full Move type of the event, e.g. `0x...::lemons::LemonCreated`.
+    pub r#type: String,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventKind {
+    /// `0x2::display::DisplayCreated<T>` for a type of the package, e.g. `Display<Lemons>`.
+    DisplayCreated(DisplayCreated),
+    /// `0x2::display::VersionUpdated<T>` for a type of the package.
+    DisplayVersionUpdated(DisplayVersionUpdated),
+    /// Any other event declared in one of the package modules, e.g. `lemons` or `ljc`.
+    Package {
+        module: String,
+        name: String,
+        fields: Value,
+    },
+    /// Event which doesn't concern the package.
+    Other { fields: Value },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayCreated {
+    /// Id of the created `Display` object.
+    pub id: ObjectID,
+    /// Type the display is created for, e.g. `0x...::lemons::Lemons`.
+    #[serde(default)]
+    pub object_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayVersionUpdated {
+    /// Id of the updated `Display` object.
+    pub id: ObjectID,
+    pub version: u16,
+    #[serde(default)]
+    pub object_type: String,
+}
+
+impl EventRecord {
+    /// Classifies `event` as an event of `package` only if its type, or the type the framework
+    /// event is emitted for, is declared in `package`.
+    pub fn new(step: &str, event: &SuiEvent, package: ObjectID) -> Self {
+        let fields = event.parsed_json.clone();
+        let kind = classify(&event.type_, fields, package);
+
+        Self {
+            step: step.to_string(),
+            tx_digest: event.id.tx_digest.to_string(),
+            sender: event.sender,
+            r#type: event.type_.to_string(),
+            kind,
+        }
+    }
+}
+
+fn classify(r#type: &StructTag, fields: Value, package: ObjectID) -> EventKind {
+    let package = AccountAddress::from(package);
+    if r#type.address == package {
+        return EventKind::Package {
+            module: r#type.module.to_string(),
+            name: r#type.name.to_string(),
+            fields,
+        };
+    }
+
+    let object_type = match r#type.type_params.as_slice() {
+        [TypeTag::Struct(object_type)] if object_type.address == package => object_type.to_string(),
+        _ => return EventKind::Other { fields },
+    };
+    if r#type.address != SUI_FRAMEWORK_ADDRESS || r#type.module.as_str() != "display" {
+        return EventKind::Other { fields };
+    }
+
+    match r#type.name.as_str() {
+        "DisplayCreated" => decode(fields, |event: DisplayCreated| {
+            EventKind::DisplayCreated(DisplayCreated {
+                object_type,
+                ..event
+            })
+        }),
+        "VersionUpdated" => decode(fields, |event: DisplayVersionUpdated| {
+            EventKind::DisplayVersionUpdated(DisplayVersionUpdated {
+                object_type,
+                ..event
+            })
+        }),
+        _ => EventKind::Other { fields },
+    }
+}
+
+/// Decodes `fields` into typed event, keeping them as is if they don't match the type.
+fn decode<T: DeserializeOwned>(fields: Value, f: impl FnOnce(T) -> EventKind) -> EventKind {
+    match serde_json::from_value(fields.clone()) {
+        Ok(event) => f(event),
+        Err(_) => EventKind::Other { fields },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use move_core_types::identifier::Identifier;
+    use serde_json::json;
+
+    use super::*;
+
+    fn struct_tag(address: AccountAddress, module: &str, name: &str) -> StructTag {
+        StructTag {
+            address,
+            module: Identifier::new(module).unwrap(),
+            name: Identifier::new(name).unwrap(),
+            type_params: vec![],
+        }
+    }
+
+    fn display_event(name: &str, object_type: StructTag) -> StructTag {
+        StructTag {
+            type_params: vec![TypeTag::Struct(Box::new(object_type))],
+            ..struct_tag(SUI_FRAMEWORK_ADDRESS, "display", name)
+        }
+    }
+
+    #[test]
+    fn event_of_package_is_classified_by_module_and_name() {
+        let package = ObjectID::random();
+        let r#type = struct_tag(package.into(), "lemons", "LemonCreated");
+        let fields = json!({ "id": "0x1" });
+
+        match classify(&r#type, fields.clone(), package) {
+            EventKind::Package {
+                module,
+                name,
+                fields: actual,
+            } => {
+                assert_eq!(module, "lemons");
+                assert_eq!(name, "LemonCreated");
+                assert_eq!(actual, fields);
+            }
+            kind => panic!("Unexpected kind {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn display_created_for_package_type_is_decoded() {
+        let package = ObjectID::random();
+        let id = ObjectID::random();
+        let object_type = struct_tag(package.into(), "lemons", "Lemons");
+        let r#type = display_event("DisplayCreated", object_type.clone());
+
+        match classify(&r#type, json!({ "id": id }), package) {
+            EventKind::DisplayCreated(event) => {
+                assert_eq!(event.id, id);
+                assert_eq!(event.object_type, object_type.to_string());
+            }
+            kind => panic!("Unexpected kind {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn display_version_updated_for_package_type_is_decoded() {
+        let package = ObjectID::random();
+        let id = ObjectID::random();
+        let object_type = struct_tag(package.into(), "ljc", "Juice");
+        let r#type = display_event("VersionUpdated", object_type.clone());
+        let fields = json!({ "id": id, "version": 2, "fields": { "contents": [] } });
+
+        match classify(&r#type, fields, package) {
+            EventKind::DisplayVersionUpdated(event) => {
+                assert_eq!(event.id, id);
+                assert_eq!(event.version, 2);
+                assert_eq!(event.object_type, object_type.to_string());
+            }
+            kind => panic!("Unexpected kind {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn display_event_for_foreign_type_is_other() {
+        let package = ObjectID::random();
+        let object_type = struct_tag(ObjectID::random().into(), "lemons", "Lemons");
+        let r#type = display_event("DisplayCreated", object_type);
+        let fields = json!({ "id": ObjectID::random() });
+
+        match classify(&r#type, fields.clone(), package) {
+            EventKind::Other { fields: actual } => assert_eq!(actual, fields),
+            kind => panic!("Unexpected kind {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn display_event_with_malformed_fields_is_other() {
+        let package = ObjectID::random();
+        let object_type = struct_tag(package.into(), "lemons", "Lemons");
+        let r#type = display_event("VersionUpdated", object_type);
+        let fields = json!({ "id": "not an id", "version": "one" });
+
+        match classify(&r#type, fields.clone(), package) {
+            EventKind::Other { fields: actual } => assert_eq!(actual, fields),
+            kind => panic!("Unexpected kind {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn decode_keeps_fields_which_do_not_match_type() {
+        let fields = json!({ "version": 1 });
+
+        match decode(fields.clone(), EventKind::DisplayCreated) {
+            EventKind::Other { fields: actual } => assert_eq!(actual, fields),
+            kind => panic!("Unexpected kind {kind:?}"),
+        }
+    }
+}
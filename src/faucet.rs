@@ -0,0 +1,61 @@
+use eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use tracing::{info, instrument};
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+enum FaucetRequest {
+    FixedAmountRequest { recipient: String },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FaucetResponse {
+    #[serde(default)]
+    transferred_gas_objects: Vec<TransferredGasObject>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TransferredGasObject {
+    amount: u64,
+    id: ObjectID,
+}
+
+/// Asks faucet at `url` to send gas to `recipient` and returns ids of sent coins.
+#[instrument(name = "Requesting gas from faucet")]
+pub async fn request_gas(url: &str, recipient: SuiAddress) -> Result<Vec<ObjectID>> {
+    let request = FaucetRequest::FixedAmountRequest {
+        recipient: recipient.to_string(),
+    };
+    let response: FaucetResponse = reqwest::Client::new()
+        .post(format!("{}/gas", url.trim_end_matches('/')))
+        .json(&request)
+        .send()
+        .await
+        .wrap_err("Failed to send request to faucet")?
+        .error_for_status()
+        .wrap_err("Faucet responded with error status")?
+        .json()
+        .await
+        .wrap_err("Failed to deserialize faucet response")?;
+
+    if let Some(error) = response.error {
+        bail!("Faucet failed to send gas: {error}");
+    }
+
+    let total: u64 = response
+        .transferred_gas_objects
+        .iter()
+        .map(|object| object.amount)
+        .sum();
+    info!("Faucet sent {total} MIST to {recipient}");
+
+    Ok(response
+        .transferred_gas_objects
+        .into_iter()
+        .map(|object| object.id)
+        .collect())
+}
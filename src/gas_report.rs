@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use sui_sdk::rpc_types::SuiTransactionBlockEffectsV1;
+use sui_types::base_types::SuiAddress;
+
+/// Gas spent by every transaction executed during deployment.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GasReport {
+    pub gas_owner: Option<SuiAddress>,
+    pub balance_before: u128,
+    pub balance_after: u128,
+    pub transactions: Vec<GasReportEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasReportEntry {
+    pub step: String,
+    /// Digest of the transaction, empty for aggregated entries of several transactions.
+    pub tx_digest: String,
+    /// Number of transactions the entry accounts for.
+    #[serde(default = "default_tx_count")]
+    pub tx_count: usize,
+    pub computation_cost: u64,
+    pub storage_cost: u64,
+    pub storage_rebate: u64,
+}
+
+fn default_tx_count() -> usize {
+    1
+}
+
+impl GasReportEntry {
+    /// Amount actually charged from gas owner, might be negative if rebate exceeds costs.
+    pub fn net(&self) -> i128 {
+        self.computation_cost as i128 + self.storage_cost as i128 - self.storage_rebate as i128
+    }
+}
+
+impl GasReport {
+    pub fn record(&mut self, step: &str, effects: &SuiTransactionBlockEffectsV1) {
+        self.transactions.push(GasReportEntry {
+            step: step.to_string(),
+            tx_digest: effects.transaction_digest.to_string(),
+            tx_count: 1,
+            computation_cost: effects.gas_used.computation_cost,
+            storage_cost: effects.gas_used.storage_cost,
+            storage_rebate: effects.gas_used.storage_rebate,
+        });
+    }
+
+    /// Entries aggregated by step, in order of first execution.
+    pub fn per_step(&self) -> Vec<GasReportEntry> {
+        let mut ret: Vec<GasReportEntry> = Vec::new();
+        for entry in &self.transactions {
+            match ret.iter_mut().find(|e| e.step == entry.step) {
+                Some(aggregated) => {
+                    aggregated.computation_cost += entry.computation_cost;
+                    aggregated.storage_cost += entry.storage_cost;
+                    aggregated.storage_rebate += entry.storage_rebate;
+                    aggregated.tx_count += entry.tx_count;
+                    aggregated.tx_digest.clear();
+                }
+                None => ret.push(entry.clone()),
+            }
+        }
+
+        ret
+    }
+
+    pub fn total(&self) -> i128 {
+        self.transactions.iter().map(GasReportEntry::net).sum()
+    }
+
+    pub fn print_table(&self) {
+        println!(
+            "{:<24} {:<46} {:>4} {:>14} {:>14} {:>14} {:>14}",
+            "step", "tx", "txs", "computation", "storage", "rebate", "net"
+        );
+        for entry in self.per_step() {
+            println!(
+                "{:<24} {:<46} {:>4} {:>14} {:>14} {:>14} {:>14}",
+                entry.step,
+                entry.tx_digest,
+                entry.tx_count,
+                entry.computation_cost,
+                entry.storage_cost,
+                entry.storage_rebate,
+                entry.net()
+            );
+        }
+        println!(
+            "{:<24} {:<46} {:>4} {:>59}",
+            "total",
+            "",
+            self.transactions.len(),
+            self.total()
+        );
+        if let Some(gas_owner) = self.gas_owner {
+            println!(
+                "balance of {gas_owner}: {} -> {} MIST",
+                self.balance_before, self.balance_after
+            );
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use eyre::{bail, ensure, Result};
+use sui_sdk::rpc_types::Coin;
+use sui_types::base_types::ObjectID;
+
+use crate::config::GasSelectionStrategy;
+use crate::constants::MAX_GAS_PAYMENT_OBJECTS;
+
+/// Selects coins which together cover `amount` and returns them along with the rest of coins.
+///
+/// Coins from `exclude` are never selected, since an object can't be used both as transaction
+/// input and gas payment. Selected coins are smashed into the first one on execution.
+pub fn select_gas_coins(
+    coins: Vec<Coin>,
+    amount: u64,
+    exclude: &[ObjectID],
+    strategy: GasSelectionStrategy,
+) -> Result<(Vec<Coin>, Vec<Coin>)> {
+    let (excluded, mut candidates): (Vec<_>, Vec<_>) = coins
+        .into_iter()
+        .partition(|coin| exclude.contains(&coin.coin_object_id));
+
+    let total: u128 = candidates.iter().map(|coin| coin.balance as u128).sum();
+    ensure!(
+        total >= amount as u128,
+        "Total balance of gas coins {total} is lower than required amount {amount}"
+    );
+
+    let selected_idx = match strategy {
+        GasSelectionStrategy::SmallestSufficient => {
+            candidates.sort_unstable_by_key(|coin| coin.balance);
+            match candidates.iter().position(|coin| coin.balance >= amount) {
+                Some(idx) => vec![idx],
+                None => fewest(&candidates, amount),
+            }
+        }
+        GasSelectionStrategy::Largest => {
+            candidates.sort_unstable_by_key(|coin| coin.balance);
+            accumulate(&candidates, amount, (0..candidates.len()).rev())
+        }
+        GasSelectionStrategy::Fewest => {
+            candidates.sort_unstable_by_key(|coin| coin.balance);
+            fewest(&candidates, amount)
+        }
+    };
+
+    if selected_idx.len() > MAX_GAS_PAYMENT_OBJECTS {
+        bail!(
+            "Required amount {amount} needs {} gas coins, but only {MAX_GAS_PAYMENT_OBJECTS} are allowed, merge coins first",
+            selected_idx.len()
+        );
+    }
+
+    let (mut selected, mut rest) = (Vec::new(), excluded);
+    for (idx, coin) in candidates.into_iter().enumerate() {
+        match selected_idx.iter().position(|&selected| selected == idx) {
+            Some(order) => selected.push((order, coin)),
+            None => rest.push(coin),
+        }
+    }
+    selected.sort_unstable_by_key(|(order, _)| *order);
+
+    Ok((selected.into_iter().map(|(_, coin)| coin).collect(), rest))
+}
+
+/// Indices of as few coins as possible covering `amount`, `coins` must be sorted by balance.
+fn fewest(coins: &[Coin], amount: u64) -> Vec<usize> {
+    let mut ret = accumulate(coins, amount, (0..coins.len()).rev());
+    // the last picked coin can be replaced by the smallest one that still covers the rest
+    if let Some(last) = ret.pop() {
+        let covered: u64 = ret.iter().map(|&idx| coins[idx].balance).sum();
+        let rest = amount.saturating_sub(covered);
+        let replacement = (0..=last)
+            .find(|idx| !ret.contains(idx) && coins[*idx].balance >= rest)
+            .unwrap_or(last);
+        ret.push(replacement);
+    }
+
+    ret
+}
+
+fn accumulate(coins: &[Coin], amount: u64, order: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut ret = Vec::new();
+    let mut covered = 0u64;
+    for idx in order {
+        if covered >= amount {
+            break;
+        }
+        covered = covered.saturating_add(coins[idx].balance);
+        ret.push(idx);
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_types::base_types::{ObjectDigest, SequenceNumber, TransactionDigest};
+
+    use super::*;
+
+    fn coin(balance: u64) -> Coin {
+        Coin {
+            coin_type: "0x2::sui::SUI".to_string(),
+            coin_object_id: ObjectID::random(),
+            version: SequenceNumber::new(),
+            digest: ObjectDigest::random(),
+            balance,
+            locked_until_epoch: None,
+            previous_transaction: TransactionDigest::random(),
+        }
+    }
+
+    fn balances(coins: &[Coin]) -> Vec<u64> {
+        coins.iter().map(|coin| coin.balance).collect()
+    }
+
+    fn select(coin_balances: &[u64], amount: u64, strategy: GasSelectionStrategy) -> Vec<u64> {
+        let coins = coin_balances.iter().copied().map(coin).collect();
+        let (selected, _) = select_gas_coins(coins, amount, &[], strategy).unwrap();
+        balances(&selected)
+    }
+
+    #[test]
+    fn smallest_sufficient_picks_single_coin() {
+        let selected = select(
+            &[50, 10, 30, 100],
+            25,
+            GasSelectionStrategy::SmallestSufficient,
+        );
+        assert_eq!(selected, vec![30]);
+    }
+
+    #[test]
+    fn smallest_sufficient_falls_back_to_fewest_coins() {
+        let selected = select(
+            &[1, 1, 1, 40, 30],
+            60,
+            GasSelectionStrategy::SmallestSufficient,
+        );
+        assert_eq!(selected, vec![40, 30]);
+    }
+
+    #[test]
+    fn smallest_sufficient_doesnt_pick_dust() {
+        let mut coins = vec![1; MAX_GAS_PAYMENT_OBJECTS * 2];
+        coins.push(500);
+        coins.push(500);
+        let selected = select(&coins, 600, GasSelectionStrategy::SmallestSufficient);
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn largest_picks_largest_coins_first() {
+        let selected = select(&[10, 50, 20, 40], 60, GasSelectionStrategy::Largest);
+        assert_eq!(selected, vec![50, 40]);
+    }
+
+    #[test]
+    fn fewest_replaces_last_coin_with_smallest_sufficient() {
+        let selected = select(&[10, 50, 20, 40], 60, GasSelectionStrategy::Fewest);
+        assert_eq!(selected, vec![50, 10]);
+    }
+
+    #[test]
+    fn excluded_coins_are_never_selected() {
+        let coins = vec![coin(100), coin(30), coin(40)];
+        let exclude = [coins[0].coin_object_id];
+        let (selected, rest) =
+            select_gas_coins(coins, 60, &exclude, GasSelectionStrategy::Largest).unwrap();
+        assert_eq!(balances(&selected), vec![40, 30]);
+        assert_eq!(balances(&rest), vec![100]);
+    }
+
+    #[test]
+    fn rest_contains_unselected_coins() {
+        let coins = vec![coin(10), coin(50), coin(20)];
+        let (selected, mut rest) =
+            select_gas_coins(coins, 15, &[], GasSelectionStrategy::SmallestSufficient).unwrap();
+        rest.sort_unstable_by_key(|coin| coin.balance);
+        assert_eq!(balances(&selected), vec![20]);
+        assert_eq!(balances(&rest), vec![10, 50]);
+    }
+
+    #[test]
+    fn insufficient_balance_fails() {
+        let coins = vec![coin(10), coin(20)];
+        let ret = select_gas_coins(coins, 31, &[], GasSelectionStrategy::Fewest);
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn too_many_coins_fail() {
+        let coins = vec![1; MAX_GAS_PAYMENT_OBJECTS + 1];
+        let coins = coins.into_iter().map(coin).collect();
+        let amount = MAX_GAS_PAYMENT_OBJECTS as u64 + 1;
+        let ret = select_gas_coins(coins, amount, &[], GasSelectionStrategy::Largest);
+        assert!(ret.is_err());
+    }
+}
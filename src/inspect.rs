@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use eyre::{eyre, Result, WrapErr};
+use serde_json::Value;
+use sui_sdk::rpc_types::{SuiObjectData, SuiObjectDataOptions, SuiParsedData};
+use sui_types::base_types::ObjectID;
+use tracing::instrument;
+
+use crate::chain_client::ChainClient;
+use crate::deployment_record::DeploymentRecord;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OutputFormat {
+    Tree,
+    Json,
+}
+
+/// Resolves `target` either as object id or as a key of the last deployment record, e.g.
+/// `lemon_registry` or the name of a package from config.
+pub fn resolve_object_id(client: &ChainClient, target: &str) -> Result<ObjectID> {
+    if let Ok(ret) = ObjectID::from_str(target) {
+        return Ok(ret);
+    }
+
+    let chain_id = &client.chain_id;
+    let record = DeploymentRecord::last(&client.base_dir, chain_id)?
+        .ok_or_else(|| eyre!("There is no deployment record for chain {chain_id}"))?;
+    if let Some(ret) = record.packages.get(target) {
+        return Ok(*ret);
+    }
+
+    record
+        .publish_result
+        .entries()?
+        .into_iter()
+        .find(|(key, _)| key == target)
+        .map(|(_, id)| id)
+        .ok_or_else(|| eyre!("`{target}` is neither object id nor key of publish result"))
+}
+
+#[instrument(name = "Fetching object", skip(client))]
+pub async fn fetch_object(client: &ChainClient, id: ObjectID) -> Result<SuiObjectData> {
+    let options = SuiObjectDataOptions::new()
+        .with_type()
+        .with_owner()
+        .with_previous_transaction()
+        .with_content();
+
+    client
+        .client
+        .read_api()
+        .get_object_with_options(id, options)
+        .await
+        .wrap_err_with(|| format!("Failed to get object with id {id}"))?
+        .data
+        .ok_or_else(|| eyre!("Object {id} doesn't exist"))
+}
+
+#[instrument(name = "Inspecting object", skip(client))]
+pub async fn inspect(client: &ChainClient, target: &str, format: OutputFormat) -> Result<()> {
+    let id = resolve_object_id(client, target)?;
+    let object = fetch_object(client, id).await?;
+
+    match format {
+        OutputFormat::Json => {
+            let json =
+                serde_json::to_string_pretty(&object).wrap_err("Failed to serialize object")?;
+            println!("{json}");
+        }
+        OutputFormat::Tree => print_tree(&object)?,
+    }
+
+    Ok(())
+}
+
+fn print_tree(object: &SuiObjectData) -> Result<()> {
+    let unknown = || "unknown".to_string();
+    println!("id: {}", object.object_id);
+    println!("version: {}", object.version);
+    println!(
+        "type: {}",
+        object
+            .type_
+            .as_ref()
+            .map_or_else(unknown, ToString::to_string)
+    );
+    println!(
+        "owner: {}",
+        object
+            .owner
+            .as_ref()
+            .map_or_else(unknown, ToString::to_string)
+    );
+    println!(
+        "previous tx: {}",
+        object
+            .previous_transaction
+            .as_ref()
+            .map_or_else(unknown, ToString::to_string)
+    );
+
+    match &object.content {
+        Some(SuiParsedData::MoveObject(content)) => {
+            let fields =
+                serde_json::to_value(&content.fields).wrap_err("Failed to serialize fields")?;
+            println!("fields:");
+            print_value(&fields, 1);
+        }
+        Some(SuiParsedData::Package(package)) => {
+            println!("modules:");
+            for module in package.disassembled.keys() {
+                println!("  {module}");
+            }
+        }
+        None => println!("content: none"),
+    }
+
+    Ok(())
+}
+
+fn print_value(value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if value.is_object() || value.is_array() {
+                    println!("{indent}{key}:");
+                    print_value(value, depth + 1);
+                } else {
+                    println!("{indent}{key}: {}", scalar(value));
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                if value.is_object() || value.is_array() {
+                    println!("{indent}-");
+                    print_value(value, depth + 1);
+                } else {
+                    println!("{indent}- {}", scalar(value));
+                }
+            }
+        }
+        _ => println!("{indent}{}", scalar(value)),
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,112 @@
+use std::str::FromStr;
+
+use bip32::DerivationPath;
+use eyre::{ensure, eyre, Result, WrapErr};
+use sui_keys::keystore::AccountKeystore;
+use sui_types::base_types::SuiAddress;
+use tracing::{info, instrument};
+
+use crate::chain_client::ChainClient;
+use crate::config::{set_active_address, AppConfig, KeyScheme};
+use crate::keystore::{load_keystore, with_keystore_mut};
+
+#[instrument(name = "Generating key", skip(config))]
+pub fn generate(
+    config: &AppConfig,
+    scheme: KeyScheme,
+    derivation_path: Option<String>,
+) -> Result<()> {
+    let derivation_path = parse_derivation_path(derivation_path)?;
+    let (address, phrase, _) = with_keystore_mut(&config.sui, |keystore| {
+        keystore
+            .generate_and_add_new_key(scheme.into(), derivation_path)
+            .map_err(|e| eyre!(e))
+    })
+    .wrap_err("Failed to generate new key")?;
+
+    info!("Generated new key for address {address}");
+    println!("Address: {address}");
+    println!("Recovery phrase: {phrase}");
+
+    Ok(())
+}
+
+#[instrument(name = "Importing key", skip(config))]
+pub fn import(
+    config: &AppConfig,
+    scheme: KeyScheme,
+    derivation_path: Option<String>,
+) -> Result<()> {
+    let derivation_path = parse_derivation_path(derivation_path)?;
+    let phrase =
+        rpassword::prompt_password("Mnemonic phrase: ").wrap_err("Failed to read mnemonic")?;
+    let address = with_keystore_mut(&config.sui, |keystore| {
+        keystore
+            .import_from_mnemonic(phrase.trim(), scheme.into(), derivation_path)
+            .map_err(|e| eyre!(e))
+    })
+    .wrap_err("Failed to import key from mnemonic")?;
+
+    info!("Imported key for address {address}");
+    println!("Address: {address}");
+
+    Ok(())
+}
+
+/// Prints keys with their balances. Works even if configured active address or sponsor isn't in
+/// the keystore, so the active key can be fixed with `set-active` afterwards.
+#[instrument(name = "Listing keys", skip_all)]
+pub async fn list(config: &AppConfig) -> Result<()> {
+    let keystore = load_keystore(&config.sui).wrap_err("Failed to load keystore")?;
+    let client = ChainClient::connect(&config.sui)
+        .await
+        .wrap_err("Failed to connect to Sui node")?;
+
+    let addresses = keystore.addresses();
+    let active_address = match config.sui.active_address.as_deref() {
+        None => addresses.last().copied(),
+        Some(address) => SuiAddress::from_str(address).ok(),
+    };
+    for address in addresses {
+        let balance = client
+            .client
+            .coin_read_api()
+            .get_balance(address, None)
+            .await
+            .wrap_err_with(|| format!("Failed to get SUI balance for address: {address}"))?;
+        let marker = if Some(address) == active_address {
+            "*"
+        } else {
+            " "
+        };
+        println!("{marker} {address} {} MIST", balance.total_balance);
+    }
+
+    Ok(())
+}
+
+#[instrument(name = "Setting active key", skip(config))]
+pub fn set_active(config: &AppConfig, address: &str) -> Result<()> {
+    let address = SuiAddress::from_str(address)
+        .map_err(|e| eyre!(e))
+        .wrap_err("Failed to parse address")?;
+    let keystore = load_keystore(&config.sui).wrap_err("Failed to load keystore")?;
+    ensure!(
+        keystore.addresses().contains(&address),
+        "Address {address} isn't present in keystore"
+    );
+
+    set_active_address(&config.path, &address.to_string()).wrap_err("Failed to update config")?;
+    info!("Active address is {address}");
+
+    Ok(())
+}
+
+fn parse_derivation_path(derivation_path: Option<String>) -> Result<Option<DerivationPath>> {
+    derivation_path
+        .as_deref()
+        .map(DerivationPath::from_str)
+        .transpose()
+        .map_err(|e| eyre!(e))
+        .wrap_err("Failed to parse derivation path")
+}
@@ -0,0 +1,86 @@
+use std::str::FromStr;
+
+use bip32::DerivationPath;
+use eyre::{bail, eyre, Result, WrapErr};
+use sui_keys::keystore::{AccountKeystore, FileBasedKeystore, InMemKeystore, Keystore};
+use sui_types::crypto::{EncodeDecodeBase64, SuiKeyPair};
+use tracing::instrument;
+
+use crate::config::{KeystoreSource, SuiConfig};
+use crate::encrypted_keystore::{unlock_keystore, update_keystore};
+
+#[instrument(name = "Loading keystore", skip_all)]
+pub fn load_keystore(config: &SuiConfig) -> Result<Keystore> {
+    match &config.keystore_source {
+        KeystoreSource::Home => {
+            let path = config
+                .keystore_path()
+                .wrap_err("Failed to get keystore path")?;
+            let ret = FileBasedKeystore::new(&path).map_err(|e| eyre!(e))?;
+
+            Ok(ret.into())
+        }
+        KeystoreSource::File { path } => {
+            let ret = FileBasedKeystore::new(&config.resolve_path(path)?).map_err(|e| eyre!(e))?;
+
+            Ok(ret.into())
+        }
+        KeystoreSource::Encrypted { path, password_var } => {
+            unlock_keystore(&config.resolve_path(path)?, password_var.as_deref())
+                .wrap_err("Failed to unlock encrypted keystore")
+        }
+        KeystoreSource::Env { var } => {
+            let encoded = std::env::var(var)
+                .wrap_err_with(|| format!("Failed to read private key from env var `{var}`"))?;
+            let keypair = SuiKeyPair::decode_base64(encoded.trim())
+                .map_err(|e| eyre!(e))
+                .wrap_err("Failed to decode base64 private key")?;
+            let mut ret = InMemKeystore::default();
+            ret.add_key(keypair)
+                .map_err(|e| eyre!(e))
+                .wrap_err("Failed to add private key into keystore")?;
+
+            Ok(Keystore::InMem(ret))
+        }
+        KeystoreSource::Mnemonic {
+            var,
+            key_scheme,
+            derivation_path,
+        } => {
+            let phrase = std::env::var(var)
+                .wrap_err_with(|| format!("Failed to read mnemonic from env var `{var}`"))?;
+            let derivation_path = derivation_path
+                .as_deref()
+                .map(DerivationPath::from_str)
+                .transpose()
+                .map_err(|e| eyre!(e))
+                .wrap_err("Failed to parse derivation path")?;
+            let mut ret = InMemKeystore::default();
+            ret.import_from_mnemonic(phrase.trim(), (*key_scheme).into(), derivation_path)
+                .map_err(|e| eyre!(e))
+                .wrap_err("Failed to import key from mnemonic")?;
+
+            Ok(Keystore::InMem(ret))
+        }
+    }
+}
+
+/// Applies `f` to the configured keystore and persists changes made by it.
+pub fn with_keystore_mut<T>(
+    config: &SuiConfig,
+    f: impl FnOnce(&mut Keystore) -> Result<T>,
+) -> Result<T> {
+    match &config.keystore_source {
+        // file based keystore saves itself on every change
+        KeystoreSource::Home | KeystoreSource::File { .. } => {
+            let mut keystore = load_keystore(config)?;
+            f(&mut keystore)
+        }
+        KeystoreSource::Encrypted { path, password_var } => {
+            update_keystore(&config.resolve_path(path)?, password_var.as_deref(), f)
+        }
+        KeystoreSource::Env { .. } | KeystoreSource::Mnemonic { .. } => {
+            bail!("Keystore from environment variable is read-only")
+        }
+    }
+}
@@ -1,37 +1,316 @@
 #![feature(box_patterns)]
 
-use crate::config::load_config;
-use crate::transaction::TryIntoEffects;
+use crate::chain_client::ChainClient;
+use crate::cli::{Cli, Command, KeysCommand};
+use crate::config::{load_config, AppConfig};
+use crate::deployer::{build_and_compile_package, package_digest};
+use crate::deployment_record::DeploymentRecord;
+use crate::package_graph::{publish_order, PackageStaging};
+use crate::transaction::{AssertSuccess, TryIntoEffects};
+use clap::Parser;
 use deployer::Deployer;
 use eyre::{eyre, Result, WrapErr};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::time::Duration;
+use sui_types::base_types::{ObjectID, ObjectType};
+use tracing::{info, instrument};
 
+mod chain_client;
+mod cli;
 mod config;
 mod constants;
 mod deployer;
+mod deployment_record;
+mod dynamic_fields;
+mod encrypted_keystore;
+mod events;
+mod faucet;
+mod gas_report;
+mod gas_selection;
+mod inspect;
+mod keys;
+mod keystore;
 mod object_parsers;
+mod package_graph;
+mod preflight;
+mod production_guard;
 mod publish_result;
+mod snapshot;
 mod telemetry;
 mod transaction;
+mod verification;
+mod watch;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let subscriber = telemetry::get_subscriber("backend".into(), "info".into(), std::io::stdout);
+    let cli = Cli::parse();
+    let mut config = load_config(&cli.config).wrap_err("Failed to load app config")?;
+    cli.build
+        .apply_to(&mut config.sui.build)
+        .wrap_err("Failed to apply build options")?;
+    let log_format = cli.log_format.unwrap_or(config.log.format);
+    let log_file = cli
+        .log_file
+        .map(Ok)
+        .or_else(|| {
+            config
+                .log
+                .file
+                .as_ref()
+                .map(|file| config.sui.resolve_path(file))
+        })
+        .transpose()
+        .wrap_err("Failed to resolve log file path")?
+        .map(File::create)
+        .transpose()
+        .wrap_err("Failed to create log file")?;
+    let otlp_tracer = cli
+        .otlp_endpoint
+        .or_else(|| config.log.otlp_endpoint.clone())
+        .map(|endpoint| telemetry::get_otlp_tracer("backend".into(), endpoint))
+        .transpose()
+        .wrap_err("Failed to init OpenTelemetry tracer")?;
+    let subscriber = telemetry::get_subscriber(
+        "backend".into(),
+        config.log.level.clone(),
+        log_format,
+        log_file,
+        otlp_tracer,
+        std::io::stdout,
+    );
     telemetry::init_subscriber(subscriber).wrap_err("Failed to init tracing subscriber")?;
-    let config = load_config().wrap_err("Failed to load app config")?;
+
+    let command = cli.command.unwrap_or(Command::Deploy { force: false });
+    if command.sends_transactions() {
+        production_guard::confirm_production(&config.sui, cli.confirm_production)?;
+    }
+
+    let ret = run(command, config).await;
+    telemetry::shutdown_tracer_provider();
+
+    ret
+}
+
+async fn run(command: Command, config: AppConfig) -> Result<()> {
+    match command {
+        Command::Deploy { force } => deploy(config, force).await,
+        Command::EncryptKeystore { input, output } => {
+            let input = match input {
+                Some(input) => input,
+                None => config
+                    .sui
+                    .keystore_path()
+                    .wrap_err("Failed to get keystore path")?,
+            };
+
+            encrypted_keystore::convert_keystore(&input, &output)
+                .wrap_err("Failed to encrypt keystore")
+        }
+        Command::Preflight => preflight::preflight(&config).await,
+        Command::Faucet => {
+            let required = config.sui.estimated_deployment_cost();
+            let deployer = Deployer::build(config)
+                .await
+                .wrap_err("Failed to build deployer")?;
+            deployer
+                .fund_from_faucet(required)
+                .await
+                .wrap_err("Failed to fund from faucet")
+        }
+        Command::MergeCoins { coin_type } => {
+            let mut deployer = Deployer::build(config)
+                .await
+                .wrap_err("Failed to build deployer")?;
+            let (balance, coin) = deployer
+                .merge_all_coins(&coin_type)
+                .await
+                .wrap_err("Failed to merge coins")?;
+            println!("{coin} {balance}");
+
+            Ok(())
+        }
+        Command::SplitCoin { amounts } => {
+            let mut deployer = Deployer::build(config)
+                .await
+                .wrap_err("Failed to build deployer")?;
+            let coins = deployer
+                .split_gas_coin(amounts)
+                .await
+                .wrap_err("Failed to split gas coin")?;
+            for coin in coins {
+                println!("{coin}");
+            }
+
+            Ok(())
+        }
+        Command::EnsureGasCoins { count, amount } => {
+            let mut deployer = Deployer::build(config)
+                .await
+                .wrap_err("Failed to build deployer")?;
+            let coins = deployer
+                .ensure_gas_coins(count, amount)
+                .await
+                .wrap_err("Failed to ensure gas coins")?;
+            for coin in coins {
+                println!("{} {}", coin.coin_object_id, coin.balance);
+            }
+
+            Ok(())
+        }
+        Command::Inspect { target, format } => {
+            let client = ChainClient::connect(&config.sui)
+                .await
+                .wrap_err("Failed to connect to Sui node")?;
+            inspect::inspect(&client, &target, format)
+                .await
+                .wrap_err("Failed to inspect object")
+        }
+        Command::DynamicFields {
+            target,
+            cursor,
+            limit,
+            all,
+        } => {
+            let client = ChainClient::connect(&config.sui)
+                .await
+                .wrap_err("Failed to connect to Sui node")?;
+            dynamic_fields::print_dynamic_fields(&client, &target, cursor, limit, all)
+                .await
+                .wrap_err("Failed to enumerate dynamic fields")
+        }
+        Command::Snapshot { output } => {
+            let client = ChainClient::connect(&config.sui)
+                .await
+                .wrap_err("Failed to connect to Sui node")?;
+            snapshot::snapshot(&client, output)
+                .await
+                .wrap_err("Failed to take snapshot")
+        }
+        Command::Watch {
+            module,
+            event_type,
+            output,
+            cursor_file,
+            interval_ms,
+        } => {
+            let client = ChainClient::connect(&config.sui)
+                .await
+                .wrap_err("Failed to connect to Sui node")?;
+            let options = watch::WatchOptions {
+                module,
+                event_type,
+                output,
+                cursor_file,
+                interval: Duration::from_millis(interval_ms),
+            };
+            watch::watch(&client, options)
+                .await
+                .wrap_err("Failed to watch events")
+        }
+        Command::Keys { command } => match command {
+            KeysCommand::Generate {
+                scheme,
+                derivation_path,
+            } => keys::generate(&config, scheme, derivation_path),
+            KeysCommand::Import {
+                scheme,
+                derivation_path,
+            } => keys::import(&config, scheme, derivation_path),
+            KeysCommand::List => keys::list(&config).await,
+            KeysCommand::SetActive { address } => keys::set_active(&config, &address),
+        },
+    }
+}
+
+#[instrument(name = "Deploying package", skip_all)]
+async fn deploy(config: AppConfig, force: bool) -> Result<()> {
+    let cap_owners = config
+        .sui
+        .cap_owners()
+        .wrap_err("Invalid `cap_owners` config")?;
     let mut deployer = Deployer::build(config.clone())
         .await
         .wrap_err("Failed to build deployer")?;
-
     let move_package_path = config
         .sui
         .move_package_path()
         .wrap_err("Failed to get path to move package")?;
+    let (dependencies, modules) = build_and_compile_package(&move_package_path, &config.sui.build)?;
+    // packages from config are built from their sources too, main package only refers to them
+    let mut packages_modules = vec![modules.clone()];
+    for package in &config.sui.packages {
+        let path = config
+            .sui
+            .resolve_path(&package.path)
+            .wrap_err_with(|| format!("Failed to get path to package `{}`", package.name))?;
+        let (_, modules) = build_and_compile_package(&path, &config.sui.build)?;
+        packages_modules.push(modules);
+    }
+    let package_digest = package_digest(&dependencies, &packages_modules);
+
+    let last_record = DeploymentRecord::last(&config.sui.base_dir, &deployer.chain_id)
+        .wrap_err("Failed to read last deployment record")?;
+    if let Some(mut record) = last_record {
+        if record.package_digest == package_digest && !force {
+            info!(
+                "Bytecode is unchanged since deployment of package {}, skipping publish",
+                record.publish_result.package
+            );
+
+            let pending = record
+                .pending_cap_owners(&cap_owners)
+                .wrap_err("Invalid `cap_owners` config")?;
+            if !pending.is_empty() {
+                deployer
+                    .transfer_caps(&record.publish_result, &pending)
+                    .await
+                    .wrap_err("Failed to transfer caps")?;
+                record.cap_owners.extend(pending);
+                record
+                    .to_file(&DeploymentRecord::path(
+                        &config.sui.base_dir,
+                        &deployer.chain_id,
+                    ))
+                    .wrap_err("Failed to update deployment record")?;
+            }
+
+            return Ok(());
+        }
+    }
+
+    if config.faucet.is_some() {
+        deployer
+            .fund_from_faucet(config.sui.estimated_deployment_cost())
+            .await
+            .wrap_err("Failed to fund from faucet")?;
+    }
+    deployer.gas_report.gas_owner = Some(deployer.gas_owner);
+    deployer.gas_report.balance_before = deployer
+        .sui_balance(deployer.gas_owner)
+        .await?
+        .total_balance;
+
+    let mut staging = PackageStaging::new()?;
+    let packages = publish_packages(&mut deployer, &config, &mut staging)
+        .await
+        .wrap_err("Failed to publish packages")?;
+    let (dependencies, modules) = if packages.is_empty() {
+        (dependencies, modules)
+    } else {
+        let staged_path = staging
+            .stage(&move_package_path)
+            .wrap_err("Failed to stage move package")?;
+        build_and_compile_package(&staged_path, &config.sui.build)?
+    };
+
     let effects = deployer
-        .publish_package(&move_package_path)
+        .publish_package(dependencies, modules)
         .await
         .wrap_err("Failed to publish package")?
         .try_into_effects()
         .wrap_err("Failed to convert into effects")?;
+    drop(staging);
 
     let created_objects: Vec<_> = deployer
         .process_published_objects(effects)
@@ -49,10 +328,89 @@ async fn main() -> Result<()> {
 
     dbg!(result);
 
+    verification::verify_publish_result(&deployer.client, &result, deployer.active_address)
+        .await
+        .wrap_err("Failed to verify published objects")?;
+
     deployer
         .setup_package(result)
         .await
         .wrap_err("Failed to setup package")?;
 
+    // saved before transferring caps, so published objects aren't lost if transfer fails
+    let mut record = DeploymentRecord {
+        publish_result: result,
+        packages,
+        gas_report: deployer.gas_report.clone(),
+        events: deployer.event_records(result.package),
+        cap_owners: BTreeMap::new(),
+        chain_id: deployer.chain_id.clone(),
+        package_digest,
+    };
+    let record_path = DeploymentRecord::path(&config.sui.base_dir, &deployer.chain_id);
+    record
+        .to_file(&record_path)
+        .wrap_err("Failed to save deployment record")?;
+
+    deployer
+        .transfer_caps(&result, &cap_owners)
+        .await
+        .wrap_err("Failed to transfer caps")?;
+
+    deployer.gas_report.balance_after = deployer
+        .sui_balance(deployer.gas_owner)
+        .await?
+        .total_balance;
+    deployer.gas_report.print_table();
+
+    record.cap_owners = cap_owners;
+    record.gas_report = deployer.gas_report.clone();
+    record.events = deployer.event_records(result.package);
+    record
+        .to_file(&record_path)
+        .wrap_err("Failed to update deployment record")?;
+
     Ok(())
 }
+
+/// Publishes packages from config in order of their dependencies and points manifests of their
+/// staged copies at published addresses, so the main package is built against them.
+#[instrument(name = "Publishing packages", skip_all)]
+async fn publish_packages(
+    deployer: &mut Deployer,
+    config: &AppConfig,
+    staging: &mut PackageStaging,
+) -> Result<BTreeMap<String, ObjectID>> {
+    let mut ret = BTreeMap::new();
+    for package in publish_order(&config.sui.packages)? {
+        let path = config
+            .sui
+            .resolve_path(&package.path)
+            .wrap_err_with(|| format!("Failed to get path to package `{}`", package.name))?;
+        let staged_path = staging
+            .stage(&path)
+            .wrap_err_with(|| format!("Failed to stage package `{}`", package.name))?;
+        let (dependencies, modules) = build_and_compile_package(&staged_path, &config.sui.build)?;
+        let effects = deployer
+            .publish_package(dependencies, modules)
+            .await
+            .wrap_err_with(|| format!("Failed to publish package `{}`", package.name))?
+            .try_into_effects()?
+            .assert_success()?;
+
+        let package_id = deployer
+            .process_published_objects(effects)
+            .await?
+            .into_iter()
+            .filter_map(|response| response.data)
+            .find(|data| matches!(data.type_, Some(ObjectType::Package)))
+            .map(|data| data.object_id)
+            .ok_or_else(|| eyre!("Package `{}` isn't found in publish effects", package.name))?;
+        info!("Package `{}` is published at {package_id}", package.name);
+
+        staging.set_published_address(&path, package_id)?;
+        ret.insert(package.name.clone(), package_id);
+    }
+
+    Ok(ret)
+}
@@ -0,0 +1,371 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use eyre::{bail, eyre, Result, WrapErr};
+use sui_types::base_types::ObjectID;
+use tempfile::TempDir;
+use tracing::{info, instrument};
+
+use crate::config::PackageConfig;
+
+/// Orders packages so every package goes after the packages it depends on.
+pub fn publish_order(packages: &[PackageConfig]) -> Result<Vec<&PackageConfig>> {
+    let mut by_name = BTreeMap::new();
+    for package in packages {
+        if by_name.insert(package.name.as_str(), package).is_some() {
+            bail!("Package `{}` is declared more than once", package.name);
+        }
+    }
+
+    let mut in_degree: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut dependents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for package in packages {
+        in_degree.entry(package.name.as_str()).or_default();
+        for dependency in &package.depends_on {
+            if !by_name.contains_key(dependency.as_str()) {
+                bail!(
+                    "Package `{}` depends on unknown package `{dependency}`",
+                    package.name
+                );
+            }
+            *in_degree.entry(package.name.as_str()).or_default() += 1;
+            dependents
+                .entry(dependency.as_str())
+                .or_default()
+                .push(package.name.as_str());
+        }
+    }
+
+    let mut ready: VecDeque<_> = in_degree
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(name, _)| *name)
+        .collect();
+    let mut ret = Vec::new();
+    while let Some(name) = ready.pop_front() {
+        ret.push(by_name[name]);
+        for dependent in dependents.get(name).into_iter().flatten() {
+            let degree = in_degree
+                .get_mut(dependent)
+                .ok_or_else(|| eyre!("Package `{dependent}` is missing in graph"))?;
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push_back(*dependent);
+            }
+        }
+    }
+
+    if ret.len() != packages.len() {
+        let cyclic: Vec<_> = in_degree
+            .into_iter()
+            .filter(|(_, degree)| *degree > 0)
+            .map(|(name, _)| name)
+            .collect();
+        bail!("Packages have cyclic dependencies: {}", cyclic.join(", "));
+    }
+
+    Ok(ret)
+}
+
+/// Copies of packages in a temporary directory with manifests pointing at published
+/// dependencies, so `Move.toml` files of the operator are never edited. Copies are removed on
+/// drop, an interrupted deployment leaves only a stale temporary directory behind.
+pub struct PackageStaging {
+    dir: TempDir,
+    /// Canonical path of original package to path of its copy.
+    copies: BTreeMap<PathBuf, PathBuf>,
+}
+
+impl PackageStaging {
+    pub fn new() -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("contracts_deployer")
+            .tempdir()
+            .wrap_err("Failed to create temporary directory for packages")?;
+
+        Ok(Self {
+            dir,
+            copies: BTreeMap::new(),
+        })
+    }
+
+    /// Copies package at `package_path` and points its local dependencies at copies of already
+    /// staged packages, or at absolute paths of the original ones. Returns path of the copy.
+    #[instrument(name = "Staging package", skip(self))]
+    pub fn stage(&mut self, package_path: &Path) -> Result<PathBuf> {
+        let original = canonical(package_path)?;
+        let copy = self.dir.path().join(self.copies.len().to_string());
+        copy_package(&original, &copy)
+            .wrap_err_with(|| format!("Failed to copy package {}", original.display()))?;
+
+        let manifest_path = copy.join("Move.toml");
+        let content =
+            std::fs::read_to_string(&manifest_path).wrap_err("Failed to read Move.toml")?;
+        let rewritten = set_local_dependencies(&content, &original, |dependency| {
+            self.copies.get(dependency).cloned()
+        })?;
+        std::fs::write(&manifest_path, rewritten).wrap_err("Failed to write Move.toml")?;
+
+        self.copies.insert(original, copy.clone());
+
+        Ok(copy)
+    }
+
+    /// Points `Move.toml` of the staged copy of package at its published address, so dependent
+    /// packages are built against the published package instead of bundling its modules.
+    pub fn set_published_address(&self, package_path: &Path, package_id: ObjectID) -> Result<()> {
+        let copy = self
+            .copies
+            .get(&canonical(package_path)?)
+            .ok_or_else(|| eyre!("Package {} isn't staged", package_path.display()))?;
+        let manifest_path = copy.join("Move.toml");
+        let content =
+            std::fs::read_to_string(&manifest_path).wrap_err("Failed to read Move.toml")?;
+        let rewritten = set_published_address(&content, package_id)?;
+
+        std::fs::write(&manifest_path, rewritten).wrap_err("Failed to write Move.toml")
+    }
+}
+
+fn canonical(path: &Path) -> Result<PathBuf> {
+    std::fs::canonicalize(path)
+        .wrap_err_with(|| format!("Failed to resolve path {}", path.display()))
+}
+
+/// Copies package sources without build artifacts.
+fn copy_package(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to).wrap_err("Failed to create directory")?;
+    for entry in std::fs::read_dir(from).wrap_err("Failed to read directory")? {
+        let entry = entry.wrap_err("Failed to read directory entry")?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            if entry.file_name() != "build" {
+                copy_package(&path, &target)?;
+            }
+        } else {
+            std::fs::copy(&path, &target)
+                .wrap_err_with(|| format!("Failed to copy {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces `local` paths of dependencies, relative to `package_path`, with `staged` path of the
+/// dependency or with its absolute path if it isn't staged.
+fn set_local_dependencies(
+    content: &str,
+    package_path: &Path,
+    staged: impl Fn(&Path) -> Option<PathBuf>,
+) -> Result<String> {
+    let mut document = content
+        .parse::<toml_edit::Document>()
+        .wrap_err("Failed to parse Move.toml")?;
+
+    for section in ["dependencies", "dev-dependencies"] {
+        let Some(dependencies) = document
+            .get_mut(section)
+            .and_then(|item| item.as_table_like_mut())
+        else {
+            continue;
+        };
+        for (_, dependency) in dependencies.iter_mut() {
+            let Some(local) = dependency
+                .as_table_like_mut()
+                .and_then(|dependency| dependency.get_mut("local"))
+            else {
+                continue;
+            };
+            let Some(path) = local.as_str().map(|path| package_path.join(path)) else {
+                continue;
+            };
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            let path = staged(&path).unwrap_or(path);
+            *local = toml_edit::value(path.to_string_lossy().as_ref());
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+/// Sets `published-at` and replaces every `0x0` named address, which are addresses of the
+/// package itself, with `package_id`.
+#[instrument(name = "Updating Move.toml with published address", skip(content))]
+fn set_published_address(content: &str, package_id: ObjectID) -> Result<String> {
+    let mut document = content
+        .parse::<toml_edit::Document>()
+        .wrap_err("Failed to parse Move.toml")?;
+
+    let address = package_id.to_string();
+    document["package"]["published-at"] = toml_edit::value(address.as_str());
+    if let Some(addresses) = document
+        .get_mut("addresses")
+        .and_then(|item| item.as_table_like_mut())
+    {
+        for (name, value) in addresses.iter_mut() {
+            let is_unpublished = value
+                .as_str()
+                .and_then(|value| ObjectID::from_hex_literal(value).ok())
+                .map_or(false, |id| id == ObjectID::ZERO);
+            if is_unpublished {
+                info!("Setting address `{name}` to {address}");
+                *value = toml_edit::value(address.as_str());
+            }
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, depends_on: &[&str]) -> PackageConfig {
+        PackageConfig {
+            name: name.to_string(),
+            path: format!("../{name}"),
+            depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    fn names(packages: Vec<&PackageConfig>) -> Vec<&str> {
+        packages
+            .into_iter()
+            .map(|package| package.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn dependencies_go_first() {
+        let packages = vec![
+            package("app", &["utils", "token"]),
+            package("token", &["utils"]),
+            package("utils", &[]),
+        ];
+        let order = names(publish_order(&packages).unwrap());
+        assert_eq!(order, vec!["utils", "token", "app"]);
+    }
+
+    #[test]
+    fn independent_packages_are_ordered_by_name() {
+        let packages = vec![package("b", &[]), package("a", &[])];
+        let order = names(publish_order(&packages).unwrap());
+        assert_eq!(order, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn unknown_dependency_fails() {
+        let packages = vec![package("app", &["missing"])];
+        let error = publish_order(&packages).unwrap_err();
+        assert!(error.to_string().contains("unknown package `missing`"));
+    }
+
+    #[test]
+    fn duplicate_package_fails() {
+        let packages = vec![package("app", &[]), package("app", &[])];
+        assert!(publish_order(&packages).is_err());
+    }
+
+    #[test]
+    fn cycle_fails() {
+        let packages = vec![
+            package("a", &["b"]),
+            package("b", &["c"]),
+            package("c", &["a"]),
+            package("d", &[]),
+        ];
+        let error = publish_order(&packages).unwrap_err();
+        assert!(error.to_string().contains("a, b, c"));
+    }
+
+    #[test]
+    fn self_dependency_fails() {
+        let packages = vec![package("a", &["a"])];
+        assert!(publish_order(&packages).is_err());
+    }
+
+    const MANIFEST: &str = r#"[package]
+name = "Utils"
+version = "0.0.1"
+
+[dependencies]
+Sui = { git = "https://github.com/MystenLabs/sui.git", subdir = "crates/sui-framework", rev = "devnet" }
+Token = { local = "../token" }
+
+[addresses]
+utils = "0x0"
+sui = "0x2"
+"#;
+
+    #[test]
+    fn published_address_replaces_zero_addresses() {
+        let package_id = ObjectID::from_hex_literal("0xabc").unwrap();
+        let rewritten = set_published_address(MANIFEST, package_id).unwrap();
+        let document = rewritten.parse::<toml_edit::Document>().unwrap();
+
+        let address = package_id.to_string();
+        assert_eq!(
+            document["package"]["published-at"].as_str(),
+            Some(address.as_str())
+        );
+        assert_eq!(
+            document["addresses"]["utils"].as_str(),
+            Some(address.as_str())
+        );
+        assert_eq!(document["addresses"]["sui"].as_str(), Some("0x2"));
+    }
+
+    #[test]
+    fn published_address_keeps_rest_of_manifest() {
+        let package_id = ObjectID::from_hex_literal("0xabc").unwrap();
+        let rewritten = set_published_address(MANIFEST, package_id).unwrap();
+        assert!(rewritten.contains(r#"Token = { local = "../token" }"#));
+        assert!(rewritten.contains(r#"name = "Utils""#));
+    }
+
+    #[test]
+    fn local_dependencies_point_at_staged_copies() {
+        let staged_token = PathBuf::from("/tmp/staged/token");
+        let rewritten = set_local_dependencies(MANIFEST, Path::new("/work/utils"), |path| {
+            (path == Path::new("/work/utils/../token")).then(|| staged_token.clone())
+        })
+        .unwrap();
+        let document = rewritten.parse::<toml_edit::Document>().unwrap();
+
+        assert_eq!(
+            document["dependencies"]["Token"]["local"].as_str(),
+            Some("/tmp/staged/token")
+        );
+        assert!(document["dependencies"]["Sui"].get("local").is_none());
+    }
+
+    #[test]
+    fn staging_doesnt_touch_original_manifest() {
+        let root = tempfile::tempdir().unwrap();
+        let package_path = root.path().join("utils");
+        std::fs::create_dir_all(package_path.join("sources")).unwrap();
+        std::fs::create_dir_all(package_path.join("build")).unwrap();
+        std::fs::write(package_path.join("Move.toml"), MANIFEST).unwrap();
+        std::fs::write(
+            package_path.join("sources/utils.move"),
+            "module utils::utils {}",
+        )
+        .unwrap();
+
+        let mut staging = PackageStaging::new().unwrap();
+        let copy = staging.stage(&package_path).unwrap();
+        let package_id = ObjectID::from_hex_literal("0xabc").unwrap();
+        staging
+            .set_published_address(&package_path, package_id)
+            .unwrap();
+
+        let original = std::fs::read_to_string(package_path.join("Move.toml")).unwrap();
+        assert_eq!(original, MANIFEST);
+        assert!(copy.join("sources/utils.move").exists());
+        assert!(!copy.join("build").exists());
+        let staged = std::fs::read_to_string(copy.join("Move.toml")).unwrap();
+        assert!(staged.contains(&package_id.to_string()));
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt::Display;
+
+use eyre::{bail, Result, WrapErr};
+use sui_sdk::rpc_types::SuiObjectDataOptions;
+use sui_sdk::{SuiClient, SuiClientBuilder};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use tracing::{info, instrument, warn};
+
+use crate::config::AppConfig;
+use crate::deployer::{build_and_compile_package, resolve_addresses};
+use crate::keystore::load_keystore;
+
+/// Collects results of checks, so all problems are reported at once.
+#[derive(Default)]
+struct Report {
+    problems: Vec<String>,
+}
+
+impl Report {
+    fn check<T, E: Display>(&mut self, name: &str, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(ret) => {
+                println!("[ok]   {name}");
+                Some(ret)
+            }
+            Err(e) => {
+                println!("[fail] {name}: {e:#}");
+                warn!("Preflight check `{name}` failed: {e:#}");
+                self.problems.push(format!("{name}: {e:#}"));
+                None
+            }
+        }
+    }
+}
+
+/// Verifies that everything needed for deployment is in place without sending transactions.
+#[instrument(name = "Running preflight checks", skip_all)]
+pub async fn preflight(config: &AppConfig) -> Result<()> {
+    let mut report = Report::default();
+
+    let client = report.check(
+        "node is reachable",
+        SuiClientBuilder::default()
+            .build(config.sui.node_url.clone())
+            .await
+            .wrap_err_with(|| format!("Failed to connect to {}", config.sui.node_url)),
+    );
+
+    if let Some(client) = &client {
+        report.check(
+            "chain id matches config",
+            check_chain_id(client, config).await,
+        );
+    }
+
+    let addresses = report
+        .check(
+            "keystore is valid",
+            load_keystore(&config.sui).wrap_err("Failed to load keystore"),
+        )
+        .and_then(|keystore| {
+            report.check(
+                "active address and sponsor are in keystore",
+                resolve_addresses(&keystore, &config.sui),
+            )
+        });
+
+    let package = report.check(
+        "move package builds",
+        config
+            .sui
+            .move_package_path()
+            .and_then(|path| build_and_compile_package(&path, &config.sui.build)),
+    );
+
+    if let (Some(client), Some((dependencies, _))) = (&client, &package) {
+        report.check(
+            "published dependencies exist on-chain",
+            check_dependencies(client, dependencies).await,
+        );
+    }
+
+    if let (Some(client), Some((_, gas_owner))) = (&client, addresses) {
+        report.check(
+            "balance covers estimated gas",
+            check_balance(client, gas_owner, config.sui.estimated_deployment_cost()).await,
+        );
+    }
+
+    report.check("cap owners are valid", config.sui.cap_owners());
+
+    if !report.problems.is_empty() {
+        bail!(
+            "Preflight found {} problem(s):\n{}",
+            report.problems.len(),
+            report.problems.join("\n")
+        );
+    }
+
+    info!("All preflight checks passed");
+    Ok(())
+}
+
+async fn check_chain_id(client: &SuiClient, config: &AppConfig) -> Result<()> {
+    let actual = client
+        .read_api()
+        .get_chain_identifier()
+        .await
+        .wrap_err("Failed to get chain identifier")?;
+
+    match &config.sui.chain_id {
+        None => bail!("`chain_id` isn't configured, node reports `{actual}`"),
+        Some(expected) if *expected != actual => {
+            bail!("Expected chain id `{expected}`, but node reports `{actual}`")
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+async fn check_dependencies(client: &SuiClient, dependencies: &[ObjectID]) -> Result<()> {
+    let mut missing = Vec::new();
+    for id in dependencies {
+        let response = client
+            .read_api()
+            .get_object_with_options(*id, SuiObjectDataOptions::new())
+            .await
+            .wrap_err_with(|| format!("Failed to get object with id {id}"))?;
+        if response.data.is_none() {
+            missing.push(id.to_string());
+        }
+    }
+
+    if !missing.is_empty() {
+        bail!("Dependencies not found: {}", missing.join(", "));
+    }
+
+    Ok(())
+}
+
+async fn check_balance(client: &SuiClient, gas_owner: SuiAddress, required: u64) -> Result<()> {
+    let balance = client
+        .coin_read_api()
+        .get_balance(gas_owner, None)
+        .await
+        .wrap_err_with(|| format!("Failed to get SUI balance for address: {gas_owner}"))?
+        .total_balance;
+
+    if balance < required as u128 {
+        bail!("Balance of {gas_owner} is {balance}, but deployment needs about {required}");
+    }
+
+    Ok(())
+}
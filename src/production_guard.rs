@@ -0,0 +1,49 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+use eyre::{bail, ensure, Result, WrapErr};
+use tracing::{info, instrument};
+
+use crate::config::SuiConfig;
+
+/// Makes sure operator really wants to send transactions to a production network.
+///
+/// Passes for non-production profiles. Production profiles must declare chain id, which operator
+/// is asked to type unless it's `confirmed` by flag.
+#[instrument(name = "Confirming production deployment", skip(config))]
+pub fn confirm_production(config: &SuiConfig, confirmed: bool) -> Result<()> {
+    if !config.production {
+        return Ok(());
+    }
+
+    let Some(chain_id) = config.chain_id.as_deref() else {
+        bail!("Production profile must declare `chain_id`");
+    };
+    if confirmed {
+        return Ok(());
+    }
+    ensure!(
+        std::io::stdin().is_terminal(),
+        "Profile is marked as production, pass `--confirm-production` to deploy non-interactively"
+    );
+
+    print!(
+        "You are about to send transactions to production network {}.\nType chain id `{chain_id}` to continue: ",
+        config.node_url
+    );
+    std::io::stdout()
+        .flush()
+        .wrap_err("Failed to flush stdout")?;
+
+    let mut answer = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .wrap_err("Failed to read confirmation")?;
+    ensure!(
+        answer.trim() == chain_id,
+        "Production deployment isn't confirmed"
+    );
+
+    info!("Production deployment confirmed");
+    Ok(())
+}
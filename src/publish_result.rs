@@ -1,6 +1,7 @@
 use derive_builder::Builder;
 use eyre::{Result, WrapErr};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use sui_types::base_types::ObjectID;
@@ -31,6 +32,14 @@ impl PublishResult {
         Ok(())
     }
 
+    /// Pairs of field name and object id, e.g. `("lemon_registry", 0x...)`.
+    pub fn entries(&self) -> Result<Vec<(String, ObjectID)>> {
+        let value = serde_json::to_value(self).wrap_err("Failed to serialize publish result")?;
+        serde_json::from_value::<BTreeMap<String, ObjectID>>(value)
+            .map(|entries| entries.into_iter().collect())
+            .wrap_err("Failed to deserialize publish result into entries")
+    }
+
     pub fn from_file(path: PathBuf) -> Result<Self> {
         let file = std::fs::File::open(path).wrap_err("Failed to open file with publish result")?;
         serde_json::from_reader(file).wrap_err("Failed to deserialize file into struct")
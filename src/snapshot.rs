@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use sui_sdk::rpc_types::SuiObjectData;
+use sui_types::base_types::ObjectID;
+use sui_types::dynamic_field::DynamicFieldInfo;
+use tracing::{info, instrument};
+
+use crate::chain_client::ChainClient;
+use crate::constants::{SNAPSHOTS_DIR, SNAPSHOT_VERSION};
+use crate::deployment_record::DeploymentRecord;
+use crate::inspect::fetch_object;
+
+/// Full on-chain state of deployed objects at some moment.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Format version, bumped on incompatible changes.
+    pub version: u32,
+    pub chain_id: String,
+    /// Unix timestamp in seconds.
+    pub taken_at: u64,
+    pub objects: BTreeMap<String, ObjectSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObjectSnapshot {
+    pub object: SuiObjectData,
+    pub dynamic_fields: Vec<DynamicFieldSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DynamicFieldSnapshot {
+    pub info: DynamicFieldInfo,
+    pub object: SuiObjectData,
+}
+
+impl Snapshot {
+    /// Takes snapshot of every object from the last deployment record of client's chain.
+    #[instrument(name = "Taking snapshot", skip_all)]
+    pub async fn take(client: &ChainClient) -> Result<Self> {
+        let record =
+            DeploymentRecord::last(&client.base_dir, &client.chain_id)?.ok_or_else(|| {
+                eyre!(
+                    "There is no deployment record for chain {}",
+                    client.chain_id
+                )
+            })?;
+
+        let mut objects = BTreeMap::new();
+        let entries = record
+            .publish_result
+            .entries()?
+            .into_iter()
+            .chain(record.packages.into_iter());
+        for (key, id) in entries {
+            let snapshot = snapshot_object(client, id)
+                .await
+                .wrap_err_with(|| format!("Failed to take snapshot of `{key}`"))?;
+            objects.insert(key, snapshot);
+        }
+
+        let taken_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .wrap_err("System time is before unix epoch")?
+            .as_secs();
+
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            chain_id: client.chain_id.clone(),
+            taken_at,
+            objects,
+        })
+    }
+
+    /// Default path of the snapshot relative to `base_dir`, e.g.
+    /// `snapshots/35834a8a-1684000000.json`.
+    pub fn default_path(&self, base_dir: &Path) -> PathBuf {
+        base_dir
+            .join(SNAPSHOTS_DIR)
+            .join(format!("{}-{}.json", self.chain_id, self.taken_at))
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).wrap_err("Failed to create directory")?;
+        }
+        let file = File::create(path).wrap_err("Failed to create file")?;
+        serde_json::to_writer_pretty(file, self).wrap_err("Failed to serialize data into file")
+    }
+}
+
+async fn snapshot_object(client: &ChainClient, id: ObjectID) -> Result<ObjectSnapshot> {
+    let object = fetch_object(client, id).await?;
+
+    let mut dynamic_fields = Vec::new();
+    for info in client.get_all_dynamic_fields(id).await? {
+        let object = fetch_object(client, info.object_id).await?;
+        dynamic_fields.push(DynamicFieldSnapshot { info, object });
+    }
+
+    Ok(ObjectSnapshot {
+        object,
+        dynamic_fields,
+    })
+}
+
+/// Takes snapshot and writes it into `output` or into default location.
+pub async fn snapshot(client: &ChainClient, output: Option<PathBuf>) -> Result<()> {
+    let snapshot = Snapshot::take(client).await?;
+    let path = match output {
+        Some(path) => path,
+        None => snapshot.default_path(&client.base_dir),
+    };
+    snapshot.to_file(&path)?;
+    info!(
+        "Snapshot of {} objects is written into {}",
+        snapshot.objects.len(),
+        path.display()
+    );
+
+    Ok(())
+}
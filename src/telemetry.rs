@@ -1,12 +1,49 @@
 use eyre::{Result, WrapErr};
+use opentelemetry::sdk::trace::{self, Tracer};
+use opentelemetry::sdk::Resource;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use std::fs::File;
+use std::sync::Mutex;
 use tokio::task::JoinHandle;
 use tracing::{subscriber, Subscriber};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
-use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, EnvFilter, Registry};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::Layered;
+use tracing_subscriber::{fmt, fmt::MakeWriter, layer::SubscriberExt, EnvFilter, Layer, Registry};
+
+use crate::config::LogFormat;
+
+type BoxedLayer = Box<dyn Layer<Layered<EnvFilter, Registry>> + Send + Sync>;
+
+/// Create tracer exporting spans to OTLP collector at `endpoint` via gRPC.
+pub fn get_otlp_tracer(name: String, endpoint: String) -> Result<Tracer> {
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", name)])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .wrap_err("Failed to install OTLP pipeline")
+}
+
+/// Flush spans which weren't exported yet.
+pub fn shutdown_tracer_provider() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
 
 /// Compose multiple layers into a `tracing`'s subscriber.
 ///
+/// Console output is written into `sink` in the provided `format`. If `file` is provided, logs
+/// are additionally written into it as Bunyan JSON. If `otlp_tracer` is provided, spans are
+/// exported with it.
+///
 /// # Implementation Notes
 ///
 /// We are using `impl Subscriber` as return type to avoid having to spell out the actual
@@ -14,6 +51,9 @@ use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, EnvFilter, Regis
 pub fn get_subscriber<Sink>(
     name: String,
     env_filter: String,
+    format: LogFormat,
+    file: Option<File>,
+    otlp_tracer: Option<Tracer>,
     sink: Sink,
 ) -> impl Subscriber + Send + Sync
 where
@@ -21,11 +61,36 @@ where
 {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
-    let formatting_layer = BunyanFormattingLayer::new(name, sink);
-    Registry::default()
-        .with(env_filter)
-        .with(JsonStorageLayer)
-        .with(formatting_layer)
+
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+    if matches!(format, LogFormat::Json) || file.is_some() {
+        layers.push(JsonStorageLayer.boxed());
+    }
+
+    let console_layer = match format {
+        LogFormat::Json => BunyanFormattingLayer::new(name.clone(), sink).boxed(),
+        LogFormat::Pretty => fmt::layer()
+            .pretty()
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(sink)
+            .boxed(),
+        LogFormat::Compact => fmt::layer()
+            .compact()
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(sink)
+            .boxed(),
+    };
+    layers.push(console_layer);
+
+    if let Some(file) = file {
+        layers.push(BunyanFormattingLayer::new(name, Mutex::new(file)).boxed());
+    }
+
+    if let Some(tracer) = otlp_tracer {
+        layers.push(tracing_opentelemetry::layer().with_tracer(tracer).boxed());
+    }
+
+    Registry::default().with(env_filter).with(layers)
 }
 
 pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) -> Result<()> {
@@ -0,0 +1,246 @@
+use eyre::{bail, eyre, Result, WrapErr};
+use move_core_types::language_storage::{StructTag, TypeTag};
+use sui_sdk::rpc_types::SuiObjectDataOptions;
+use sui_sdk::SuiClient;
+use sui_types::base_types::{ObjectID, ObjectType, SuiAddress};
+use sui_types::object::Owner;
+use sui_types::SUI_FRAMEWORK_ADDRESS;
+use tracing::{info, instrument};
+
+use crate::publish_result::PublishResult;
+
+enum ExpectedType {
+    Package,
+    Struct {
+        /// Struct is defined in sui framework instead of the published package.
+        framework: bool,
+        module: &'static str,
+        name: &'static str,
+        /// `module::Name` of the first type parameter, both from the published package.
+        type_param: Option<(&'static str, &'static str)>,
+    },
+}
+
+enum ExpectedOwner {
+    Deployer,
+    Shared,
+    Immutable,
+}
+
+struct ExpectedObject {
+    key: &'static str,
+    id: ObjectID,
+    r#type: ExpectedType,
+    owner: Option<ExpectedOwner>,
+}
+
+const fn package_struct(
+    module: &'static str,
+    name: &'static str,
+    type_param: Option<(&'static str, &'static str)>,
+) -> ExpectedType {
+    ExpectedType::Struct {
+        framework: false,
+        module,
+        name,
+        type_param,
+    }
+}
+
+const LEMONS: Option<(&str, &str)> = Some(("lemons", "Lemons"));
+
+fn expected_objects(result: &PublishResult) -> Vec<ExpectedObject> {
+    use ExpectedOwner::*;
+
+    let object = |key, id, r#type, owner| ExpectedObject {
+        key,
+        id,
+        r#type,
+        owner,
+    };
+
+    vec![
+        object(
+            "package",
+            result.package,
+            ExpectedType::Package,
+            Some(Immutable),
+        ),
+        object(
+            "lemons_pool",
+            result.lemons_pool,
+            package_struct("lemon_pool", "LemonPool", None),
+            None,
+        ),
+        object(
+            "lemon_registry",
+            result.lemon_registry,
+            package_struct("registry", "Registry", LEMONS),
+            Some(Shared),
+        ),
+        object(
+            "lemon_randomness",
+            result.lemon_randomness,
+            package_struct("randomness", "Randomness", LEMONS),
+            None,
+        ),
+        object(
+            "lemon_mint_config",
+            result.lemon_mint_config,
+            package_struct("mint_config", "MintConfig", LEMONS),
+            Some(Shared),
+        ),
+        object(
+            "lemon_treasury",
+            result.lemon_treasury,
+            package_struct("lemons", "Treasury", None),
+            None,
+        ),
+        object(
+            "lemon_cap",
+            result.lemon_cap,
+            package_struct("admin", "AdminCap", LEMONS),
+            Some(Deployer),
+        ),
+        object(
+            "juice_cap",
+            result.juice_cap,
+            package_struct("admin", "AdminCap", Some(("ljc", "Juice"))),
+            Some(Deployer),
+        ),
+        object(
+            "juice_treasury",
+            result.juice_treasury,
+            package_struct("ljc", "JuiceTreasury", None),
+            None,
+        ),
+        object(
+            "coin_juice_treasury_cap",
+            result.coin_juice_treasury_cap,
+            ExpectedType::Struct {
+                framework: true,
+                module: "coin",
+                name: "TreasuryCap",
+                type_param: Some(("ljc", "LJC")),
+            },
+            Some(Deployer),
+        ),
+    ]
+}
+
+/// Re-fetches every object of `result` and checks its type and ownership.
+///
+/// All mismatches are collected and reported in one error.
+#[instrument(name = "Verifying published objects", skip(client, result))]
+pub async fn verify_publish_result(
+    client: &SuiClient,
+    result: &PublishResult,
+    deployer: SuiAddress,
+) -> Result<()> {
+    let mut problems = Vec::new();
+
+    for expected in expected_objects(result) {
+        let response = client
+            .read_api()
+            .get_object_with_options(
+                expected.id,
+                SuiObjectDataOptions::new().with_type().with_owner(),
+            )
+            .await
+            .wrap_err_with(|| format!("Failed to get object with id {}", expected.id))?;
+
+        let Some(data) = response.data else {
+            problems.push(format!("{} ({}) doesn't exist", expected.key, expected.id));
+            continue;
+        };
+
+        if let Err(e) = check_type(&expected.r#type, data.type_, result.package) {
+            problems.push(format!("{} ({}): {e}", expected.key, expected.id));
+        }
+        if let Some(owner) = &expected.owner {
+            if let Err(e) = check_owner(owner, data.owner, deployer) {
+                problems.push(format!("{} ({}): {e}", expected.key, expected.id));
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            "Published objects don't match expectations:\n{}",
+            problems.join("\n")
+        );
+    }
+
+    info!("All published objects are verified");
+    Ok(())
+}
+
+fn check_type(
+    expected: &ExpectedType,
+    actual: Option<ObjectType>,
+    package: ObjectID,
+) -> Result<()> {
+    let actual = actual.ok_or_else(|| eyre!("type is missing in response"))?;
+    let (framework, module, name, type_param) = match expected {
+        ExpectedType::Package => {
+            return match actual {
+                ObjectType::Package => Ok(()),
+                ObjectType::Struct(actual) => bail!("expected package, found {actual}"),
+            };
+        }
+        ExpectedType::Struct {
+            framework,
+            module,
+            name,
+            type_param,
+        } => (*framework, *module, *name, *type_param),
+    };
+
+    let tag: StructTag = actual
+        .try_into()
+        .map_err(|e| eyre!("expected {module}::{name}, found package: {e}"))?;
+    let expected_address = if framework {
+        SUI_FRAMEWORK_ADDRESS
+    } else {
+        package.into()
+    };
+
+    if tag.address != expected_address || tag.module.as_str() != module || tag.name.as_str() != name
+    {
+        bail!("expected {expected_address}::{module}::{name}, found {tag}");
+    }
+
+    if let Some((param_module, param_name)) = type_param {
+        match tag.type_params.first() {
+            Some(TypeTag::Struct(box StructTag {
+                address,
+                module,
+                name,
+                ..
+            })) if *address == package.into()
+                && module.as_str() == param_module
+                && name.as_str() == param_name => {}
+            _ => bail!("expected type parameter {param_module}::{param_name}, found {tag}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn check_owner(
+    expected: &ExpectedOwner,
+    actual: Option<Owner>,
+    deployer: SuiAddress,
+) -> Result<()> {
+    let actual = actual.ok_or_else(|| eyre!("owner is missing in response"))?;
+    match (expected, actual) {
+        (ExpectedOwner::Deployer, Owner::AddressOwner(address)) if address == deployer => Ok(()),
+        (ExpectedOwner::Shared, Owner::Shared { .. }) => Ok(()),
+        (ExpectedOwner::Immutable, Owner::Immutable) => Ok(()),
+        (ExpectedOwner::Deployer, actual) => {
+            bail!("expected to be owned by {deployer}, found {actual}")
+        }
+        (ExpectedOwner::Shared, actual) => bail!("expected to be shared, found {actual}"),
+        (ExpectedOwner::Immutable, actual) => bail!("expected to be immutable, found {actual}"),
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use eyre::{eyre, Result, WrapErr};
+use sui_sdk::rpc_types::{EventFilter, SuiEvent};
+use sui_types::event::EventID;
+use tracing::{info, instrument};
+
+use crate::chain_client::ChainClient;
+use crate::constants::{DEPLOYMENTS_DIR, WATCH_PAGE_LIMIT};
+use crate::deployment_record::DeploymentRecord;
+use crate::events::EventRecord;
+
+pub struct WatchOptions {
+    /// Only events emitted by this module of the package.
+    pub module: Option<String>,
+    /// Only events of this type, either struct name or full type.
+    pub event_type: Option<String>,
+    /// File to append JSON lines into, stdout if not provided.
+    pub output: Option<PathBuf>,
+    /// File with cursor of the last processed event.
+    pub cursor_file: Option<PathBuf>,
+    pub interval: Duration,
+}
+
+impl WatchOptions {
+    fn matches(&self, event: &SuiEvent) -> bool {
+        match &self.event_type {
+            None => true,
+            Some(event_type) => {
+                event.type_.name.as_str() == event_type || event.type_.to_string() == *event_type
+            }
+        }
+    }
+}
+
+/// Polls events emitted by the last deployed package and prints them as JSON lines until
+/// interrupted. Progress is persisted, so watching resumes after the last printed event.
+#[instrument(name = "Watching events", skip_all)]
+pub async fn watch(client: &ChainClient, options: WatchOptions) -> Result<()> {
+    let record = DeploymentRecord::last(&client.base_dir, &client.chain_id)?.ok_or_else(|| {
+        eyre!(
+            "There is no deployment record for chain {}",
+            client.chain_id
+        )
+    })?;
+    let package = record.publish_result.package;
+    let filter = match &options.module {
+        None => EventFilter::Package(package),
+        Some(module) => EventFilter::MoveModule {
+            package,
+            module: module
+                .parse()
+                .map_err(|e| eyre!("Invalid module name `{module}`: {e}"))?,
+        },
+    };
+
+    let cursor_file = match &options.cursor_file {
+        Some(path) => path.clone(),
+        None => client
+            .base_dir
+            .join(DEPLOYMENTS_DIR)
+            .join(format!("{}.cursor.json", client.chain_id)),
+    };
+    let mut cursor = load_cursor(&cursor_file)?;
+    let mut output: Box<dyn Write> = match &options.output {
+        None => Box::new(std::io::stdout()),
+        Some(path) => Box::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .wrap_err("Failed to open output file")?,
+        ),
+    };
+    info!("Watching events of package {package}");
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        let query = client.client.event_api().query_events(
+            filter.clone(),
+            cursor.clone(),
+            Some(WATCH_PAGE_LIMIT),
+            false,
+        );
+        let page = tokio::select! {
+            page = query => page.wrap_err("Failed to query events")?,
+            _ = &mut ctrl_c => break,
+        };
+
+        for event in page.data.iter().filter(|event| options.matches(event)) {
+            let line = serde_json::to_string(&EventRecord::new("watch", event, package))
+                .wrap_err("Failed to serialize event")?;
+            writeln!(output, "{line}").wrap_err("Failed to write event")?;
+        }
+        output.flush().wrap_err("Failed to flush output")?;
+
+        if let Some(last) = page.data.last() {
+            cursor = Some(last.id.clone());
+            save_cursor(&cursor_file, &last.id)?;
+        }
+        if page.has_next_page {
+            continue;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(options.interval) => {}
+            _ = &mut ctrl_c => break,
+        }
+    }
+
+    info!("Stopped watching events");
+    Ok(())
+}
+
+fn load_cursor(path: &Path) -> Result<Option<EventID>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let file = File::open(path).wrap_err("Failed to open cursor file")?;
+    serde_json::from_reader(file)
+        .map(Some)
+        .wrap_err("Failed to deserialize cursor")
+}
+
+fn save_cursor(path: &Path, cursor: &EventID) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).wrap_err("Failed to create directory")?;
+    }
+    let file = File::create(path).wrap_err("Failed to create cursor file")?;
+    serde_json::to_writer(file, cursor).wrap_err("Failed to serialize cursor")
+}